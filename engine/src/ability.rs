@@ -0,0 +1,676 @@
+use std::fmt;
+use serde::{Serialize, Deserialize};
+
+use crate::card::{AbilitiesFragment, Card, CardFragmentKind};
+use crate::event::GameEvent;
+use crate::game::{GameState, GameStep, Zone};
+
+// Small declarative language for card abilities, written one per line in
+// card data files:
+//
+//   when ETB: draw 1
+//   {T}: add {G}
+//   {2}{R}, sacrifice: deal 3 to any target
+//
+// Costs are separated by commas; several effects are separated by ';' or "then".
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManaCost
+{
+    pub generic: u32,
+    pub colors: String,   // one letter per colored symbol, e.g. "RR"
+}
+
+impl ManaCost
+{
+    pub fn total(&self) -> u32
+    {
+        self.generic + self.colors.chars().count() as u32
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trigger
+{
+    EntersBattlefield,
+    Dies,
+    Attacks,
+    Upkeep,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cost
+{
+    Tap,
+    Mana(ManaCost),
+    Sacrifice,
+    PayLife(u32),
+    Discard(u32),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Target
+{
+    AnyTarget,
+    Player,
+    EachOpponent,
+    You,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Effect
+{
+    Draw(u32),
+    AddMana(ManaCost),
+    Damage { amount: u32, target: Target },
+    GainLife(u32),
+    LoseLife(u32),
+    Mill(u32),
+    Scry(u32),
+    Surveil(u32),
+    Discard(u32),
+    ExtraTurn,
+    ExtraCombat,
+    Skip(GameStep),   // the controller's next such step
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ability
+{
+    Triggered { trigger: Trigger, effects: Vec<Effect> },
+    Activated { costs: Vec<Cost>, effects: Vec<Effect> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbilityParseError
+{
+    pub column: usize,   // 1-based, in characters
+    pub message: String,
+}
+
+impl fmt::Display for AbilityParseError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for AbilityParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token
+{
+    Word(String),     // lowercased
+    Number(u32),
+    Symbol(String),   // contents of {...}, uppercased
+    Colon,
+    Comma,
+    Semicolon,
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, AbilityParseError>
+{
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len()
+    {
+        let column = i + 1;
+        let c = chars[i];
+        match c
+        {
+            _ if c.is_whitespace() || c == '.' => { i += 1; }
+            ':' => { tokens.push((Token::Colon, column)); i += 1; }
+            ',' => { tokens.push((Token::Comma, column)); i += 1; }
+            ';' => { tokens.push((Token::Semicolon, column)); i += 1; }
+            '{' =>
+            {
+                let end = chars[i..].iter().position(|&c| c == '}')
+                    .ok_or(AbilityParseError { column, message: String::from("unclosed '{'") })?;
+                let symbol: String = chars[i + 1..i + end].iter().collect();
+                tokens.push((Token::Symbol(symbol.trim().to_ascii_uppercase()), column));
+                i += end + 1;
+            }
+            _ if c.is_ascii_digit() =>
+            {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_digit() { i += 1; }
+                let digits: String = chars[start..i].iter().collect();
+                let n = digits.parse().map_err(|_| AbilityParseError { column, message: format!("number \"{}\" is too large", digits) })?;
+                tokens.push((Token::Number(n), column));
+            }
+            _ if c.is_alphabetic() =>
+            {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '\'' || chars[i] == '-') { i += 1; }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push((Token::Word(word.to_lowercase()), column));
+            }
+            _ => return Err(AbilityParseError { column, message: format!("unexpected character '{}'", c) }),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser
+{
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    end_column: usize,
+}
+
+impl Parser
+{
+    fn peek(&self) -> Option<&Token>
+    {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn column(&self) -> usize
+    {
+        self.tokens.get(self.pos).map(|(_, c)| *c).unwrap_or(self.end_column)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, AbilityParseError>
+    {
+        Err(AbilityParseError { column: self.column(), message: message.into() })
+    }
+
+    fn next(&mut self) -> Option<Token>
+    {
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn at_word(&self, word: &str) -> bool
+    {
+        matches!(self.peek(), Some(Token::Word(w)) if w == word)
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), AbilityParseError>
+    {
+        if self.at_word(word)
+        {
+            self.pos += 1;
+            Ok(())
+        }
+        else
+        {
+            self.error(format!("expected \"{}\"", word))
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<u32, AbilityParseError>
+    {
+        match self.peek()
+        {
+            Some(Token::Number(n)) => { let n = *n; self.pos += 1; Ok(n) }
+            _ => self.error("expected a number"),
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), AbilityParseError>
+    {
+        if self.peek() == Some(&token)
+        {
+            self.pos += 1;
+            Ok(())
+        }
+        else
+        {
+            self.error(format!("expected {}", what))
+        }
+    }
+
+    fn ability(&mut self) -> Result<Ability, AbilityParseError>
+    {
+        if self.at_word("when") || self.at_word("whenever") || self.at_word("at")
+        {
+            self.pos += 1;
+            let trigger = self.trigger()?;
+            self.expect(Token::Colon, "':' after the trigger")?;
+            let effects = self.effects()?;
+            return Ok(Ability::Triggered { trigger, effects });
+        }
+
+        let costs = self.costs()?;
+        self.expect(Token::Colon, "':' after the costs")?;
+        let effects = self.effects()?;
+        Ok(Ability::Activated { costs, effects })
+    }
+
+    fn trigger(&mut self) -> Result<Trigger, AbilityParseError>
+    {
+        // Allow "when this enters", "when ETB", "at upkeep", "at the beginning of your upkeep"
+        let start = self.column();
+        while self.peek().is_some() && self.peek() != Some(&Token::Colon)
+        {
+            let Some(Token::Word(word)) = self.next() else { return Err(AbilityParseError { column: start, message: String::from("unknown trigger") }); };
+            match word.as_str()
+            {
+                "etb" | "enters" => return Ok(Trigger::EntersBattlefield),
+                "dies" => return Ok(Trigger::Dies),
+                "attacks" => return Ok(Trigger::Attacks),
+                "upkeep" => return Ok(Trigger::Upkeep),
+                "this" | "the" | "beginning" | "of" | "your" | "creature" => {}
+                _ => return Err(AbilityParseError { column: start, message: format!("unknown trigger \"{}\"", word) }),
+            }
+        }
+        Err(AbilityParseError { column: start, message: String::from("missing trigger") })
+    }
+
+    fn costs(&mut self) -> Result<Vec<Cost>, AbilityParseError>
+    {
+        let mut costs = Vec::new();
+        loop
+        {
+            let column = self.column();
+            match self.peek().cloned()
+            {
+                Some(Token::Symbol(s)) if s == "T" => { self.pos += 1; costs.push(Cost::Tap); }
+                Some(Token::Symbol(_)) => costs.push(Cost::Mana(self.mana()?)),
+                Some(Token::Word(w)) if w == "sacrifice" => { self.pos += 1; costs.push(Cost::Sacrifice); }
+                Some(Token::Word(w)) if w == "pay" =>
+                {
+                    self.pos += 1;
+                    let n = self.expect_number()?;
+                    self.expect_word("life")?;
+                    costs.push(Cost::PayLife(n));
+                }
+                Some(Token::Word(w)) if w == "discard" =>
+                {
+                    self.pos += 1;
+                    costs.push(Cost::Discard(self.expect_number()?));
+                }
+                _ => return Err(AbilityParseError { column, message: String::from("expected a cost such as {T}, {2}{R}, sacrifice or \"when <trigger>:\"") }),
+            }
+
+            if self.peek() == Some(&Token::Comma)
+            {
+                self.pos += 1;
+                continue;
+            }
+            return Ok(costs);
+        }
+    }
+
+    // One or more adjacent mana symbols, e.g. {2}{R}{R}
+    fn mana(&mut self) -> Result<ManaCost, AbilityParseError>
+    {
+        let mut cost = ManaCost::default();
+        while let Some(Token::Symbol(s)) = self.peek().cloned()
+        {
+            if let Ok(n) = s.parse::<u32>()
+            {
+                cost.generic += n;
+            }
+            else if s.len() == 1 && "WUBRGC".contains(s.as_str())
+            {
+                cost.colors.push_str(&s);
+            }
+            else if s == "T"
+            {
+                break;
+            }
+            else
+            {
+                return self.error(format!("unknown mana symbol {{{}}}", s));
+            }
+            self.pos += 1;
+        }
+        Ok(cost)
+    }
+
+    fn effects(&mut self) -> Result<Vec<Effect>, AbilityParseError>
+    {
+        let mut effects = vec![self.effect()?];
+        loop
+        {
+            match self.peek()
+            {
+                None => return Ok(effects),
+                Some(Token::Semicolon) => { self.pos += 1; }
+                Some(Token::Word(w)) if w == "then" => { self.pos += 1; }
+                _ => return self.error("expected ';' or \"then\" between effects"),
+            }
+            // "; then draw 1"
+            if self.at_word("then")
+            {
+                self.pos += 1;
+            }
+            effects.push(self.effect()?);
+        }
+    }
+
+    fn effect(&mut self) -> Result<Effect, AbilityParseError>
+    {
+        let column = self.column();
+        let Some(Token::Word(word)) = self.next() else { return Err(AbilityParseError { column, message: String::from("expected an effect") }); };
+
+        match word.as_str()
+        {
+            "draw" => Ok(Effect::Draw(self.count()?)),
+            "mill" => Ok(Effect::Mill(self.count()?)),
+            "scry" => Ok(Effect::Scry(self.count()?)),
+            "surveil" => Ok(Effect::Surveil(self.count()?)),
+            "discard" => Ok(Effect::Discard(self.count()?)),
+            "take" | "extra" =>
+            {
+                // "take an extra turn", "extra combat"
+                if word == "take"
+                {
+                    if self.at_word("an") || self.at_word("a")
+                    {
+                        self.pos += 1;
+                    }
+                    self.expect_word("extra")?;
+                }
+                if self.at_word("turn") || self.at_word("combat")
+                {
+                    let turn = self.at_word("turn");
+                    self.pos += 1;
+                    return Ok(if turn { Effect::ExtraTurn } else { Effect::ExtraCombat });
+                }
+                self.error("expected \"turn\" or \"combat\" after \"extra\"")
+            }
+            "skip" =>
+            {
+                // "skip your next draw step"
+                for filler in ["your", "next"]
+                {
+                    if self.at_word(filler)
+                    {
+                        self.pos += 1;
+                    }
+                }
+                let step = match self.peek()
+                {
+                    Some(Token::Word(w)) if w == "untap" => GameStep::Untap,
+                    Some(Token::Word(w)) if w == "upkeep" => GameStep::Upkeep,
+                    Some(Token::Word(w)) if w == "draw" => GameStep::Draw,
+                    Some(Token::Word(w)) if w == "main" => GameStep::Main,
+                    Some(Token::Word(w)) if w == "combat" => GameStep::DeclareAttackers,
+                    _ => return self.error("expected a step to skip: untap, upkeep, draw, main or combat"),
+                };
+                self.pos += 1;
+                if self.at_word("step") || self.at_word("phase")
+                {
+                    self.pos += 1;
+                }
+                Ok(Effect::Skip(step))
+            }
+            "add" =>
+            {
+                let mana = self.mana()?;
+                if mana.total() == 0
+                {
+                    return self.error("expected mana symbols after \"add\"");
+                }
+                Ok(Effect::AddMana(mana))
+            }
+            "gain" | "lose" =>
+            {
+                let n = self.expect_number()?;
+                self.expect_word("life")?;
+                Ok(if word == "gain" { Effect::GainLife(n) } else { Effect::LoseLife(n) })
+            }
+            "deal" | "deals" =>
+            {
+                let amount = self.expect_number()?;
+                if self.at_word("damage")
+                {
+                    self.pos += 1;
+                }
+                self.expect_word("to")?;
+                Ok(Effect::Damage { amount, target: self.target()? })
+            }
+            _ => Err(AbilityParseError { column, message: format!("unknown effect \"{}\"", word) }),
+        }
+    }
+
+    // Optional count, defaulting to 1 ("draw" == "draw 1"); "a card"/"cards" are ignored
+    fn count(&mut self) -> Result<u32, AbilityParseError>
+    {
+        let n = match self.peek()
+        {
+            Some(Token::Number(n)) => { let n = *n; self.pos += 1; n }
+            Some(Token::Word(w)) if w == "a" => { self.pos += 1; 1 }
+            _ => 1,
+        };
+        if self.at_word("card") || self.at_word("cards")
+        {
+            self.pos += 1;
+        }
+        Ok(n)
+    }
+
+    fn target(&mut self) -> Result<Target, AbilityParseError>
+    {
+        let column = self.column();
+        let first = match self.next() { Some(Token::Word(w)) => w, _ => String::new() };
+        let target = match first.as_str()
+        {
+            "you" => Some(Target::You),
+            "any" | "target" | "each" =>
+            {
+                match (first.as_str(), self.next())
+                {
+                    ("any", Some(Token::Word(w))) if w == "target" => Some(Target::AnyTarget),
+                    // Damage marked on creatures isn't tracked yet, so this would do nothing
+                    ("target", Some(Token::Word(w))) if w == "creature" =>
+                    {
+                        return Err(AbilityParseError { column, message: String::from("\"target creature\" isn't supported yet") });
+                    }
+                    ("target", Some(Token::Word(w))) if w == "player" || w == "opponent" => Some(Target::Player),
+                    ("each", Some(Token::Word(w))) if w == "opponent" => Some(Target::EachOpponent),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        target.ok_or(AbilityParseError { column, message: String::from("expected a target: any target, target player, each opponent or you") })
+    }
+}
+
+/// Parse one ability line.
+pub fn parse_ability(text: &str) -> Result<Ability, AbilityParseError>
+{
+    let tokens = tokenize(text)?;
+    if tokens.is_empty()
+    {
+        return Err(AbilityParseError { column: 1, message: String::from("empty ability") });
+    }
+
+    let mut parser = Parser { tokens, pos: 0, end_column: text.chars().count() + 1 };
+    let ability = parser.ability()?;
+    if parser.peek().is_some()
+    {
+        return parser.error("unexpected text after the ability");
+    }
+    Ok(ability)
+}
+
+pub fn abilities(card: &Card) -> &[Ability]
+{
+    card.fragments.get(&CardFragmentKind::Abilities)
+        .and_then(|f| f.as_any().downcast_ref::<AbilitiesFragment>())
+        .map(|af| af.abilities.as_slice())
+        .unwrap_or(&[])
+}
+
+pub fn triggered_effects(card: &Card, trigger: Trigger) -> Vec<Effect>
+{
+    abilities(card).iter()
+        .filter_map(|a| match a
+        {
+            Ability::Triggered { trigger: t, effects } if *t == trigger => Some(effects.clone()),
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+impl GameState
+{
+    /// Draw `count` cards for `player`. Drawing from an empty library ends the game.
+    pub fn draw_cards(&mut self, player: usize, count: u32) -> bool
+    {
+        for _ in 0..count
+        {
+            let Some(card) = self.players[player].zones.get_mut(&Zone::Library).unwrap().pop() else
+            {
+                self.decked_player = Some(player);
+                self.step = GameStep::GameOver;
+                return false;
+            };
+            self.players[player].zones.get_mut(&Zone::Hand).unwrap().push(card);
+        }
+        self.emit(GameEvent::Drew { player, count });
+        true
+    }
+
+    /// Resolve effects controlled by `player`. Their controller picks the
+    /// player for targeted damage and what to keep when scrying or surveilling.
+    pub fn resolve_effects(&mut self, player: usize, effects: &[Effect])
+    {
+        for effect in effects
+        {
+            vlog!(crate::ELoggingVerbosity::Verbose, "Resolve {:?}", effect);
+            match effect
+            {
+                Effect::Draw(n) => { self.draw_cards(player, *n); }
+                Effect::GainLife(n) => self.players[player].life += *n as i32,
+                Effect::LoseLife(n) => self.players[player].life -= *n as i32,
+                Effect::Mill(n) => { self.mill(player, *n as usize); }
+                Effect::Scry(n) =>
+                {
+                    let mut keep = self.top_card_choices(player, *n as usize, |c, g| c.choose_scry(g, player, *n as usize));
+                    self.scry(player, *n as usize, |_| keep.next().unwrap_or(true));
+                }
+                Effect::Surveil(n) =>
+                {
+                    let mut keep = self.top_card_choices(player, *n as usize, |c, g| c.choose_surveil(g, player, *n as usize));
+                    self.surveil(player, *n as usize, |_| keep.next().unwrap_or(true));
+                }
+                Effect::Discard(n) => { self.discard_random(player, *n as usize); }
+                Effect::Damage { amount, target } => match target
+                {
+                    Target::You => self.players[player].life -= *amount as i32,
+                    Target::AnyTarget | Target::Player =>
+                    {
+                        let chosen = self.ask(player, |c, g| c.choose_player_target(g, player, *target));
+                        if let Some(p) = self.players.get_mut(chosen)
+                        {
+                            p.life -= *amount as i32;
+                        }
+                    }
+                    Target::EachOpponent =>
+                    {
+                        for (i, p) in self.players.iter_mut().enumerate()
+                        {
+                            if i != player
+                            {
+                                p.life -= *amount as i32;
+                            }
+                        }
+                    }
+                },
+                Effect::ExtraTurn => self.turn_queue.add_extra_turn(player),
+                Effect::ExtraCombat => self.turn_queue.add_extra_combat(),
+                Effect::Skip(step) => self.turn_queue.skip_next(player, *step),
+                Effect::AddMana(_) => {}  // no mana pool yet; lands are counted directly
+            }
+        }
+
+        if self.players.iter().any(|p| p.life <= 0)
+        {
+            self.step = GameStep::GameOver;
+        }
+    }
+
+    /// Ask `player`'s controller about the top `count` cards and return the
+    /// answers in the order scry and surveil visit them (deepest card first).
+    fn top_card_choices(&mut self, player: usize, count: usize, decide: impl FnOnce(&mut dyn crate::controller::PlayerController, &GameState) -> Vec<bool>) -> std::vec::IntoIter<bool>
+    {
+        let looked_at = count.min(self.players[player].zones[&Zone::Library].len());
+        let mut keep = self.ask(player, decide);
+        keep.resize(looked_at, true);
+        keep.reverse();
+        keep.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn parses_example_abilities()
+    {
+        assert_eq!(parse_ability("when ETB: draw 1").unwrap(),
+            Ability::Triggered { trigger: Trigger::EntersBattlefield, effects: vec![Effect::Draw(1)] });
+        assert_eq!(parse_ability("{T}: add {G}").unwrap(),
+            Ability::Activated { costs: vec![Cost::Tap], effects: vec![Effect::AddMana(ManaCost { generic: 0, colors: String::from("G") })] });
+        assert_eq!(parse_ability("{2}{R}, sacrifice: deal 3 to any target").unwrap(),
+            Ability::Activated {
+                costs: vec![Cost::Mana(ManaCost { generic: 2, colors: String::from("R") }), Cost::Sacrifice],
+                effects: vec![Effect::Damage { amount: 3, target: Target::AnyTarget }],
+            });
+    }
+
+    #[test]
+    fn surveil_from_card_text_can_mill_the_top_card()
+    {
+        use crate::card::{forest, grizzly_bears, Deck};
+        use crate::controller::{Decision, ScriptedController};
+
+        let mut land = forest();
+        land.name = String::from("Lookout Grove");
+        let abilities = vec![parse_ability("when ETB: surveil 1").unwrap()];
+        land.fragments.insert(CardFragmentKind::Abilities, Box::new(AbilitiesFragment { abilities }));
+
+        let deck = Deck { cards: vec![land, grizzly_bears(), forest()], sideboard: Vec::new() };
+        let mut gs = GameState::new_stacked(&[&deck, &deck], 0);
+        gs.players[0].zones.get_mut(&Zone::Library).unwrap().push(grizzly_bears());
+        gs.set_controller(0, Box::new(ScriptedController::new([Decision::KeepOnTop(vec![false])])));
+        gs.step = GameStep::Main;
+
+        let index = gs.players[0].zones[&Zone::Hand].iter().position(|c| c.name == "Lookout Grove").unwrap();
+        assert!(gs.play_land(index));
+        assert_eq!(gs.players[0].zones[&Zone::Graveyard].iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["Grizzly Bears"]);
+        assert!(gs.players[0].zones[&Zone::Library].is_empty());
+        assert_eq!(gs.events.last(), Some(&GameEvent::Surveilled { player: 0, kept_on_top: 0, put_in_graveyard: 1 }));
+        assert_eq!(gs.controllers[0].remaining(), 0);
+    }
+
+    #[test]
+    fn turn_effects_reach_the_turn_queue()
+    {
+        assert_eq!(parse_ability("when ETB: take an extra turn; extra combat; skip your next draw step").unwrap(),
+            Ability::Triggered { trigger: Trigger::EntersBattlefield, effects: vec![Effect::ExtraTurn, Effect::ExtraCombat, Effect::Skip(GameStep::Draw)] });
+        assert_eq!(parse_ability("{T}: skip lunch").unwrap_err().column, 11);
+
+        let mut gs = GameState::new_default();
+        gs.resolve_effects(0, &[Effect::ExtraTurn, Effect::ExtraCombat, Effect::Skip(GameStep::Draw)]);
+        assert_eq!(gs.turn_queue.extra_turns, [0]);
+        assert_eq!(gs.turn_queue.extra_combats, 1);
+        assert!(gs.turn_queue.take_skip(0, GameStep::Draw));
+    }
+
+    #[test]
+    fn errors_point_at_the_column()
+    {
+        let err = parse_ability("when ETB: draw 1; explode").unwrap_err();
+        assert_eq!(err.column, 19);
+
+        let err = parse_ability("{T} add {G}").unwrap_err();
+        assert_eq!(err.column, 5);
+
+        let err = parse_ability("{R}: deal 1 to target creature").unwrap_err();
+        assert_eq!(err.column, 16);
+    }
+}
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::fmt;
+use serde::{Serialize, Deserialize};
+
+use crate::ability::{abilities, Ability, Cost, Effect};
+use crate::card::CardType;
+use crate::controller::{can_attack, can_block};
+use crate::game::{GameState, GameStep, Zone};
+
+// Above this many attackers (or blocker/attacker combinations) only a
+// representative set of combat choices is listed; `apply_action` still
+// accepts any legal one.
+const MAX_ENUMERATED_ATTACKERS: usize = 8;
+const MAX_ENUMERATED_BLOCKS: usize = 256;
+
+/// Something a player can do right now. Indices refer to the acting player's zones,
+/// except blockers, which are on the defending player's battlefield.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action
+{
+    PlayLand { zone: Zone, index: usize },
+    /// `payment` lists the battlefield indices of the lands tapped for mana.
+    Cast { zone: Zone, index: usize, payment: Vec<usize> },
+    ActivateAbility { permanent: usize, ability: usize, payment: Vec<usize> },
+    Attack(Vec<usize>),
+    /// (blocker, attacker) pairs; an empty list declares no blocks.
+    Block(Vec<(usize, usize)>),
+    PassPriority,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ActionError
+{
+    GameOver,
+    NotYourDecision { player: usize },
+    WrongStep { step: GameStep },
+    Illegal(String),
+}
+
+impl fmt::Display for ActionError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            ActionError::GameOver => write!(f, "the game is over"),
+            ActionError::NotYourDecision { player } => write!(f, "player {} can't act now", player + 1),
+            ActionError::WrongStep { step } => write!(f, "not allowed during {:?}", step),
+            ActionError::Illegal(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+fn illegal<T>(message: impl Into<String>) -> Result<T, ActionError>
+{
+    Err(ActionError::Illegal(message.into()))
+}
+
+impl GameState
+{
+    /// The player who has to act in the current step.
+    pub fn deciding_player(&self) -> usize
+    {
+        match self.step
+        {
+            GameStep::DeclareBlockers => self.defending_player(),
+            _ => self.current_player_index,
+        }
+    }
+
+    /// Untapped lands `player` could tap for `amount` mana, leaving out
+    /// `exclude` (a permanent that taps as part of the same cost).
+    pub fn default_payment(&self, player: usize, amount: u32, exclude: Option<usize>) -> Option<Vec<usize>>
+    {
+        let payment: Vec<usize> = self.players[player].zones[&Zone::Battlefield].iter().enumerate()
+            .filter(|(i, c)| Some(*i) != exclude && c.is_type(CardType::Land) && !crate::tappable::is_tapped(c))
+            .map(|(i, _)| i)
+            .take(amount as usize)
+            .collect();
+        (payment.len() == amount as usize).then_some(payment)
+    }
+
+    /// Every action `player` may take now. Casts and activations are listed
+    /// once, with the first untapped lands as payment.
+    pub fn legal_actions(&self, player: usize) -> Vec<Action>
+    {
+        if self.is_game_over() || player >= self.players.len() || player != self.deciding_player()
+        {
+            return Vec::new();
+        }
+
+        match self.step
+        {
+            GameStep::Main => self.main_phase_actions(player),
+            GameStep::DeclareAttackers => self.attack_actions(player),
+            GameStep::DeclareBlockers => self.block_actions(player),
+            _ => vec![Action::PassPriority],
+        }
+    }
+
+    fn main_phase_actions(&self, player: usize) -> Vec<Action>
+    {
+        let mut actions = Vec::new();
+        let zones = &self.players[player].zones;
+        let exile_playable = crate::exile::playable_exile_indices(&zones[&Zone::Exile], player, self.turns);
+        let sources: Vec<(Zone, usize)> = (0..zones[&Zone::Hand].len()).map(|i| (Zone::Hand, i))
+            .chain(exile_playable.into_iter().map(|i| (Zone::Exile, i)))
+            .collect();
+
+        for (zone, index) in sources
+        {
+            let card = &zones[&zone][index];
+            if card.is_type(CardType::Land)
+            {
+                if self.players[player].can_play_land()
+                {
+                    actions.push(Action::PlayLand { zone, index });
+                }
+            }
+            else if crate::creature::is_creature(card)
+                && let Some(payment) = self.default_payment(player, crate::discard::cost_in_zone(card, zone), None)
+            {
+                actions.push(Action::Cast { zone, index, payment });
+            }
+        }
+
+        for (permanent, card) in zones[&Zone::Battlefield].iter().enumerate()
+        {
+            for (ability, a) in abilities(card).iter().enumerate()
+            {
+                if let Ability::Activated { costs, effects } = a
+                    && !is_mana_ability(effects)
+                    && let Some(payment) = self.activation_payment(player, permanent, costs)
+                {
+                    actions.push(Action::ActivateAbility { permanent, ability, payment });
+                }
+            }
+        }
+
+        actions.push(Action::PassPriority);
+        actions
+    }
+
+    fn attack_actions(&self, player: usize) -> Vec<Action>
+    {
+        let eligible: Vec<usize> = self.players[player].zones[&Zone::Battlefield].iter().enumerate()
+            .filter(|(_, c)| can_attack(c))
+            .map(|(i, _)| i)
+            .collect();
+
+        if eligible.len() > MAX_ENUMERATED_ATTACKERS
+        {
+            let mut actions = vec![Action::Attack(Vec::new()), Action::Attack(eligible.clone())];
+            actions.extend(eligible.iter().map(|&i| Action::Attack(vec![i])));
+            return actions;
+        }
+
+        (0..1usize << eligible.len())
+            .map(|mask| Action::Attack(eligible.iter().enumerate()
+                .filter(|(bit, _)| mask & (1 << bit) != 0)
+                .map(|(_, &i)| i)
+                .collect()))
+            .collect()
+    }
+
+    fn block_actions(&self, player: usize) -> Vec<Action>
+    {
+        let blockers: Vec<usize> = self.players[player].zones[&Zone::Battlefield].iter().enumerate()
+            .filter(|(_, c)| can_block(c))
+            .map(|(i, _)| i)
+            .collect();
+        let attackers = &self.attacking_creatures;
+
+        let combinations = (attackers.len() + 1).checked_pow(blockers.len() as u32);
+        if combinations.is_none_or(|n| n > MAX_ENUMERATED_BLOCKS)
+        {
+            let mut actions = vec![Action::Block(Vec::new())];
+            for &b in &blockers
+            {
+                actions.extend(attackers.iter().map(|&a| Action::Block(vec![(b, a)])));
+            }
+            return actions;
+        }
+
+        let mut actions = Vec::new();
+        enumerate_blocks(&blockers, attackers, &mut Vec::new(), &mut actions);
+        actions
+    }
+
+    /// Lands to tap for an activated ability, if `player` can pay all of its costs.
+    fn activation_payment(&self, player: usize, permanent: usize, costs: &[Cost]) -> Option<Vec<usize>>
+    {
+        let card = &self.players[player].zones[&Zone::Battlefield][permanent];
+        let taps = costs.contains(&Cost::Tap);
+        if taps && (crate::tappable::is_tapped(card) || crate::creature::has_summoning_sickness(card))
+        {
+            return None;
+        }
+
+        let mut mana = 0;
+        for cost in costs
+        {
+            match cost
+            {
+                Cost::Mana(m) => mana += m.total(),
+                Cost::PayLife(n) if self.players[player].life < *n as i32 => return None,
+                Cost::Discard(n) if self.players[player].zones[&Zone::Hand].len() < *n as usize => return None,
+                _ => {}
+            }
+        }
+        self.default_payment(player, mana, taps.then_some(permanent))
+    }
+
+    /// Check `action` against the rules and perform it for `player`.
+    pub fn apply_action(&mut self, player: usize, action: Action) -> Result<(), ActionError>
+    {
+        if self.is_game_over()
+        {
+            return Err(ActionError::GameOver);
+        }
+        if player >= self.players.len() || player != self.deciding_player()
+        {
+            return Err(ActionError::NotYourDecision { player });
+        }
+
+        let step = self.step;
+        match action
+        {
+            Action::PlayLand { zone, index } if step == GameStep::Main =>
+            {
+                if zone != Zone::Hand && zone != Zone::Exile
+                {
+                    return illegal("lands are played from hand or exile");
+                }
+                if !self.play_land_from(zone, index)
+                {
+                    return illegal("that land can't be played now");
+                }
+                Ok(())
+            }
+
+            Action::Cast { zone, index, payment } if step == GameStep::Main =>
+            {
+                if zone != Zone::Hand && zone != Zone::Exile
+                {
+                    return illegal("spells are cast from hand or exile");
+                }
+                if !self.cast_paid(zone, index, &payment)
+                {
+                    return illegal("that spell can't be cast with that payment");
+                }
+                Ok(())
+            }
+
+            Action::ActivateAbility { permanent, ability, payment } if step == GameStep::Main =>
+            {
+                self.activate_ability(player, permanent, ability, &payment)
+            }
+
+            Action::Attack(attackers) if step == GameStep::DeclareAttackers =>
+            {
+                let battlefield = &self.players[player].zones[&Zone::Battlefield];
+                let mut seen = attackers.clone();
+                seen.sort_unstable();
+                seen.dedup();
+                if seen.len() != attackers.len() || attackers.iter().any(|&i| i >= battlefield.len() || !can_attack(&battlefield[i]))
+                {
+                    return illegal("every attacker must be a different creature that can attack");
+                }
+                self.commit_attackers(attackers);
+                Ok(())
+            }
+
+            Action::Block(blocks) if step == GameStep::DeclareBlockers =>
+            {
+                let battlefield = &self.players[player].zones[&Zone::Battlefield];
+                let mut blockers: Vec<usize> = blocks.iter().map(|(b, _)| *b).collect();
+                let mut attackers: Vec<usize> = blocks.iter().map(|(_, a)| *a).collect();
+                blockers.sort_unstable();
+                blockers.dedup();
+                attackers.sort_unstable();
+                attackers.dedup();
+                if blockers.len() != blocks.len() || attackers.len() != blocks.len()
+                {
+                    return illegal("each creature blocks at most once and each attacker takes one blocker");
+                }
+                if blocks.iter().any(|&(b, a)| b >= battlefield.len() || !can_block(&battlefield[b]) || !self.attacking_creatures.contains(&a))
+                {
+                    return illegal("blockers must be untapped creatures blocking an attacker");
+                }
+                self.commit_blockers(blocks.into_iter().collect::<HashMap<_, _>>());
+                Ok(())
+            }
+
+            Action::PassPriority if !matches!(step, GameStep::DeclareAttackers | GameStep::DeclareBlockers) =>
+            {
+                if step == GameStep::Main
+                {
+                    self.end_main_phase(player);
+                }
+                else
+                {
+                    self.step();
+                }
+                Ok(())
+            }
+
+            _ => Err(ActionError::WrongStep { step }),
+        }
+    }
+
+    fn activate_ability(&mut self, player: usize, permanent: usize, ability: usize, payment: &[usize]) -> Result<(), ActionError>
+    {
+        let (costs, effects) = match self.players[player].zones[&Zone::Battlefield].get(permanent)
+            .and_then(|c| abilities(c).get(ability))
+        {
+            Some(Ability::Activated { costs, effects }) if !is_mana_ability(effects) => (costs.clone(), effects.clone()),
+            _ => return illegal("no such activated ability"),
+        };
+        if self.activation_payment(player, permanent, &costs).is_none()
+        {
+            return illegal("the ability's costs can't be paid");
+        }
+
+        let mana: u32 = costs.iter().map(|c| if let Cost::Mana(m) = c { m.total() } else { 0 }).sum();
+        let taps = costs.contains(&Cost::Tap);
+        if !self.valid_payment(player, payment, mana, taps.then_some(permanent))
+        {
+            return illegal("payment must be distinct untapped lands covering the mana cost");
+        }
+
+        // Pay everything, leaving the sacrifice for last so indices stay valid
+        {
+            let battlefield = self.players[player].zones.get_mut(&Zone::Battlefield).unwrap();
+            for &i in payment
+            {
+                crate::tappable::set_tapped(&mut battlefield[i], true);
+            }
+            if taps
+            {
+                crate::tappable::set_tapped(&mut battlefield[permanent], true);
+            }
+        }
+        for cost in &costs
+        {
+            match cost
+            {
+                Cost::PayLife(n) => self.players[player].life -= *n as i32,
+                Cost::Discard(n) =>
+                {
+                    for _ in 0..*n
+                    {
+                        let index = self.ask(player, |c, g| c.choose_discard(g, player));
+                        let hand_size = self.players[player].zones[&Zone::Hand].len();
+                        self.discard(player, index.min(hand_size.saturating_sub(1)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if costs.contains(&Cost::Sacrifice)
+        {
+            self.move_from_battlefield(player, permanent, Zone::Graveyard);
+        }
+
+        self.resolve_effects(player, &effects);
+        Ok(())
+    }
+
+    /// `payment` taps exactly `amount` different untapped lands, not counting `exclude`.
+    pub(crate) fn valid_payment(&self, player: usize, payment: &[usize], amount: u32, exclude: Option<usize>) -> bool
+    {
+        let battlefield = &self.players[player].zones[&Zone::Battlefield];
+        let mut distinct = payment.to_vec();
+        distinct.sort_unstable();
+        distinct.dedup();
+
+        distinct.len() == payment.len()
+            && payment.len() == amount as usize
+            && payment.iter().all(|&i| Some(i) != exclude
+                && battlefield.get(i).is_some_and(|c| c.is_type(CardType::Land) && !crate::tappable::is_tapped(c)))
+    }
+}
+
+// Lands produce mana by being counted, so "{T}: add {G}" isn't offered as an action
+fn is_mana_ability(effects: &[Effect]) -> bool
+{
+    !effects.is_empty() && effects.iter().all(|e| matches!(e, Effect::AddMana(_)))
+}
+
+fn enumerate_blocks(blockers: &[usize], attackers: &[usize], current: &mut Vec<(usize, usize)>, out: &mut Vec<Action>)
+{
+    let Some((&blocker, rest)) = blockers.split_first() else
+    {
+        out.push(Action::Block(current.clone()));
+        return;
+    };
+
+    enumerate_blocks(rest, attackers, current, out);
+    for &attacker in attackers
+    {
+        if current.iter().all(|&(_, a)| a != attacker)
+        {
+            current.push((blocker, attacker));
+            enumerate_blocks(rest, attackers, current, out);
+            current.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears, Deck};
+
+    fn main_phase_game() -> GameState
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 2);
+        gs.step = GameStep::Main;
+        let zones = gs.zones_mut();
+        zones.get_mut(&Zone::Hand).unwrap().clear();
+        zones.get_mut(&Zone::Hand).unwrap().extend([forest(), grizzly_bears()]);
+        zones.get_mut(&Zone::Battlefield).unwrap().push(forest());
+        gs
+    }
+
+    #[test]
+    fn lists_and_applies_main_phase_actions()
+    {
+        let mut gs = main_phase_game();
+        let actions = gs.legal_actions(0);
+        assert!(actions.contains(&Action::PlayLand { zone: Zone::Hand, index: 0 }));
+        assert!(actions.contains(&Action::PassPriority));
+        assert!(!actions.iter().any(|a| matches!(a, Action::Cast { .. })), "one land can't pay for bears");
+        assert!(gs.legal_actions(1).is_empty());
+
+        gs.apply_action(0, Action::PlayLand { zone: Zone::Hand, index: 0 }).unwrap();
+        let cast = Action::Cast { zone: Zone::Hand, index: 0, payment: vec![0, 1] };
+        assert!(gs.legal_actions(0).contains(&cast));
+        assert!(gs.apply_action(0, Action::Cast { zone: Zone::Hand, index: 0, payment: vec![0, 0] }).is_err());
+        gs.apply_action(0, cast).unwrap();
+        assert_eq!(gs.zones()[&Zone::Battlefield].len(), 3);
+
+        assert_eq!(gs.apply_action(1, Action::PassPriority), Err(ActionError::NotYourDecision { player: 1 }));
+        gs.apply_action(0, Action::PassPriority).unwrap();
+        assert_eq!(gs.step, GameStep::DeclareAttackers);
+    }
+
+    #[test]
+    fn combat_choices_cover_every_subset()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 2);
+        gs.step = GameStep::DeclareAttackers;
+        gs.players[0].zones.get_mut(&Zone::Battlefield).unwrap().extend([grizzly_bears(), grizzly_bears()]);
+        gs.players[1].zones.get_mut(&Zone::Battlefield).unwrap().push(grizzly_bears());
+
+        assert_eq!(gs.legal_actions(0).len(), 4);
+        gs.apply_action(0, Action::Attack(vec![0, 1])).unwrap();
+        assert_eq!(gs.step, GameStep::DeclareBlockers);
+
+        // No block, or the one blocker on either attacker
+        let blocks = gs.legal_actions(1);
+        assert_eq!(blocks.len(), 3);
+        assert!(gs.apply_action(1, Action::Block(vec![(0, 5)])).is_err());
+        gs.apply_action(1, Action::Block(vec![(0, 1)])).unwrap();
+        assert_eq!(gs.step, GameStep::AssignDamage);
+
+        gs.step();
+        assert_eq!(gs.players[1].life, 18);
+        assert!(gs.players[1].zones[&Zone::Battlefield].is_empty());
+    }
+}
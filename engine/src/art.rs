@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Serialize, Deserialize};
+
+/// Image shown for cards without their own art.
+pub const FALLBACK_IMAGE: &str = "back.jpg";
+
+/// Get the path to the card images served by the web client
+pub fn card_images_dir_path() -> PathBuf
+{
+    crate::music::find_web_dir().join("web/cards")
+}
+
+/// MIME type for a card image file, or None if it isn't an image we serve.
+pub fn image_mime_type(file: &str) -> Option<&'static str>
+{
+    let extension = Path::new(file).extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str()
+    {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "webp" => Some("image/webp"),
+        "gif" => Some("image/gif"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+/// URL path the server exposes a card image file under.
+pub fn image_url(file: &str) -> String
+{
+    let encoded: String = file.bytes()
+        .map(|b| match b
+        {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    format!("/cards/{}", encoded)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CardImage
+{
+    pub url: String,
+    pub mime_type: String,
+}
+
+/// Maps card names to their image, built from the files in the card image directory.
+/// A file's stem is the card name: `Grizzly Bears.jpg` is the art for "Grizzly Bears".
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ArtManifest
+{
+    pub images: BTreeMap<String, CardImage>,
+    pub fallback: Option<CardImage>,
+    #[serde(skip)]
+    dir: PathBuf,
+    #[serde(skip)]
+    scanned_at: Option<SystemTime>,
+}
+
+impl ArtManifest
+{
+    pub fn scan(dir: impl AsRef<Path>) -> Self
+    {
+        let dir = dir.as_ref().to_path_buf();
+        let scanned_at = modified(&dir);
+        let mut images = BTreeMap::new();
+        let mut fallback = None;
+
+        if let Ok(entries) = std::fs::read_dir(&dir)
+        {
+            for entry in entries.flatten()
+            {
+                if !entry.file_type().is_ok_and(|t| t.is_file())
+                {
+                    continue;
+                }
+                let Some(file) = entry.file_name().to_str().map(str::to_string) else { continue; };
+                let Some(mime_type) = image_mime_type(&file) else { continue; };
+                let image = CardImage { url: image_url(&file), mime_type: mime_type.to_string() };
+
+                if file == FALLBACK_IMAGE
+                {
+                    fallback = Some(image);
+                }
+                else if let Some(name) = Path::new(&file).file_stem().and_then(|s| s.to_str())
+                {
+                    images.insert(name.to_string(), image);
+                }
+            }
+        }
+
+        ArtManifest { images, fallback, dir, scanned_at }
+    }
+
+    /// Whether files were added or removed since the manifest was built.
+    pub fn is_stale(&self) -> bool
+    {
+        modified(&self.dir) != self.scanned_at
+    }
+
+    /// Rebuild the manifest if the directory changed. Returns true if it was rebuilt.
+    pub fn refresh(&mut self) -> bool
+    {
+        if !self.is_stale()
+        {
+            return false;
+        }
+        *self = ArtManifest::scan(&self.dir);
+        true
+    }
+
+    /// The card's own art if there is any, otherwise the fallback.
+    pub fn lookup(&self, name: &str) -> Option<&CardImage>
+    {
+        self.images.get(name).or(self.fallback.as_ref())
+    }
+
+    pub fn has_art(&self, name: &str) -> bool
+    {
+        self.images.contains_key(name)
+    }
+}
+
+fn modified(dir: &Path) -> Option<SystemTime>
+{
+    std::fs::metadata(dir).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn manifest_maps_names_and_falls_back()
+    {
+        let dir = std::env::temp_dir().join(format!("tcg-art-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Grizzly Bears.jpg"), b"").unwrap();
+        std::fs::write(dir.join("back.jpg"), b"").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"").unwrap();
+
+        let manifest = ArtManifest::scan(&dir);
+        let bears = manifest.lookup("Grizzly Bears").unwrap();
+        assert_eq!(bears.url, "/cards/Grizzly%20Bears.jpg");
+        assert_eq!(bears.mime_type, "image/jpeg");
+        assert_eq!(manifest.lookup("Forest").unwrap().url, "/cards/back.jpg");
+        assert!(!manifest.has_art("notes"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
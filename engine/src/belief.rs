@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::card::{Card, CardType};
+use crate::event::GameEvent;
+use crate::game::{GameState, Zone};
+
+// How much less likely a card becomes to be in hand when the opponent could
+// have played it and didn't. Lands are almost always played; creatures are
+// sometimes held back.
+const UNPLAYED_LAND_FACTOR: f64 = 0.1;
+const UNCAST_CREATURE_FACTOR: f64 = 0.3;
+const UNUSED_TRICK_FACTOR: f64 = 0.3;
+
+// Cards a defender could cast during combat. None are playable at instant
+// speed yet; declined blocks are still recorded so the model is ready.
+fn usable_in_combat(card: &Card) -> bool
+{
+    card.has_keyword("flash")
+}
+
+/// One player's belief about an opponent's hidden cards (hand and library).
+///
+/// Decklists are open, so the unseen cards are the opponent's list minus
+/// everything in their public zones. Which of those are in hand is inferred
+/// from the plays they passed up; each card drawn since dilutes that evidence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Belief
+{
+    pub opponent: usize,
+    pub hand_size: usize,
+    pub unseen: BTreeMap<String, u32>,        // card name -> copies in hand or library
+    pub hand_weight: BTreeMap<String, f64>,   // card name -> relative odds of a copy being in hand
+}
+
+impl Belief
+{
+    /// Expected number of copies of `name` in the opponent's hand.
+    pub fn expected_in_hand(&self, name: &str) -> f64
+    {
+        let total: f64 = self.unseen.iter().map(|(n, &count)| count as f64 * self.weight(n)).sum();
+        let Some(&count) = self.unseen.get(name) else { return 0.0 };
+        if total == 0.0
+        {
+            return 0.0;
+        }
+        (count as f64 * self.weight(name) * self.hand_size as f64 / total).min(count as f64)
+    }
+
+    /// Unseen card names, most likely to be in hand first, with expected copies.
+    pub fn likely_hand(&self) -> Vec<(String, f64)>
+    {
+        let mut names: Vec<(String, f64)> = self.unseen.keys().map(|n| (n.clone(), self.expected_in_hand(n))).collect();
+        names.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        names
+    }
+
+    pub fn weight(&self, name: &str) -> f64
+    {
+        self.hand_weight.get(name).copied().unwrap_or(1.0)
+    }
+
+    /// Pick `hand_size` of `cards` for the hand, favouring likelier names.
+    /// Returns the chosen indices.
+    pub fn sample_hand(&self, cards: &[Card], rng: &mut StdRng) -> Vec<usize>
+    {
+        // Weighted sampling without replacement (Efraimidis-Spirakis keys)
+        let mut keys: Vec<(f64, usize)> = cards.iter().enumerate()
+            .map(|(i, card)| (rng.r#gen::<f64>().powf(1.0 / self.weight(&card.name).max(1e-6)), i))
+            .collect();
+        keys.sort_by(|a, b| b.0.total_cmp(&a.0));
+        keys.into_iter().take(self.hand_size).map(|(_, i)| i).collect()
+    }
+}
+
+impl GameState
+{
+    /// What `viewer` can infer about `opponent`'s hand from the public zones
+    /// and the event log.
+    pub fn belief(&self, viewer: usize, opponent: usize) -> Belief
+    {
+        let zones = &self.players[opponent].zones;
+        let mut unseen = BTreeMap::new();
+        let mut kinds: BTreeMap<String, &Card> = BTreeMap::new();
+        for card in zones[&Zone::Hand].iter().chain(&zones[&Zone::Library])
+        {
+            *unseen.entry(card.name.clone()).or_insert(0) += 1;
+            kinds.entry(card.name.clone()).or_insert(card);
+        }
+
+        let mut hand_weight: BTreeMap<String, f64> = unseen.keys().map(|n| (n.clone(), 1.0)).collect();
+        let mut hand_size = 0;
+        for event in &self.events
+        {
+            match *event
+            {
+                GameEvent::Drew { player, count } if player == opponent && player != viewer =>
+                {
+                    // The new cards carry none of the earlier evidence
+                    for _ in 0..count
+                    {
+                        hand_size += 1;
+                        let stale = (hand_size - 1) as f64 / hand_size as f64;
+                        for w in hand_weight.values_mut()
+                        {
+                            *w = 1.0 - (1.0 - *w) * stale;
+                        }
+                    }
+                }
+
+                GameEvent::PassedMain { player, hand_size: size, untapped_mana, land_drop } if player == opponent && player != viewer =>
+                {
+                    hand_size = size;
+                    for (name, w) in hand_weight.iter_mut()
+                    {
+                        let card = kinds[name];
+                        if land_drop && card.is_type(CardType::Land)
+                        {
+                            *w *= UNPLAYED_LAND_FACTOR;
+                        }
+                        else if card.is_type(CardType::Creature) && card.cost <= untapped_mana
+                        {
+                            *w *= UNCAST_CREATURE_FACTOR;
+                        }
+                    }
+                }
+
+                GameEvent::DeclinedBlocks { player, hand_size: size, untapped_mana, .. } if player == opponent && player != viewer =>
+                {
+                    // Took damage with mana up: probably no affordable trick in hand
+                    hand_size = size;
+                    for (name, w) in hand_weight.iter_mut()
+                    {
+                        let card = kinds[name];
+                        if usable_in_combat(card) && card.cost <= untapped_mana
+                        {
+                            *w *= UNUSED_TRICK_FACTOR;
+                        }
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        Belief { opponent, hand_size: zones[&Zone::Hand].len(), unseen, hand_weight }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears, Deck};
+
+    #[test]
+    fn passing_with_a_land_drop_suggests_no_lands_in_hand()
+    {
+        let deck = Deck { cards: (0..20).map(|i| if i % 2 == 0 { forest() } else { grizzly_bears() }).collect(), sideboard: Vec::new() };
+        let mut game = GameState::new_with_decks(&[&deck, &deck], 0, 1);
+        let fresh = game.belief(0, 1);
+        assert!((fresh.expected_in_hand("Forest") - fresh.expected_in_hand("Grizzly Bears")).abs() < 1.5);
+
+        game.emit(GameEvent::PassedMain { player: 1, hand_size: 7, untapped_mana: 0, land_drop: true });
+        let informed = game.belief(0, 1);
+        assert!(informed.expected_in_hand("Forest") < fresh.expected_in_hand("Forest"));
+        assert_eq!(informed.likely_hand()[0].0, "Grizzly Bears");
+
+        // A draw brings back some chance of a land
+        game.emit(GameEvent::Drew { player: 1, count: 1 });
+        assert!(game.belief(0, 1).expected_in_hand("Forest") > informed.expected_in_hand("Forest"));
+    }
+
+    #[test]
+    fn not_blocking_with_mana_up_suggests_no_trick()
+    {
+        let mut ambush = grizzly_bears();
+        ambush.name = String::from("Ambush Bears");
+        ambush.keywords.push(String::from("Flash"));
+        let defender: Vec<Card> = vec![ambush, grizzly_bears()].into_iter().chain((0..10).map(|_| forest())).collect();
+        let mut game = GameState::new_stacked(&[&Deck { cards: (0..12).map(|_| forest()).collect(), sideboard: Vec::new() }, &Deck { cards: defender, sideboard: Vec::new() }], 0);
+
+        let mut attacker = grizzly_bears();
+        crate::creature::set_summoning_sickness(&mut attacker, false);
+        game.players[0].zones.get_mut(&Zone::Battlefield).unwrap().push(attacker);
+        game.players[1].zones.get_mut(&Zone::Battlefield).unwrap().extend([forest(), forest()]);
+        game.turns = 1;
+        game.step = crate::game::GameStep::DeclareAttackers;
+        game.apply_action(0, crate::action::Action::Attack(vec![0])).unwrap();
+        game.apply_action(1, crate::action::Action::Block(Vec::new())).unwrap();
+
+        assert!(matches!(game.events.last(), Some(GameEvent::DeclinedBlocks { player: 1, untapped_mana: 2, unblocked: 1, .. })));
+        let belief = game.belief(0, 1);
+        assert!(belief.weight("Ambush Bears") < 1.0);
+        assert_eq!(belief.weight("Grizzly Bears"), 1.0);
+    }
+
+    #[test]
+    fn own_passes_are_not_evidence()
+    {
+        let mut game = GameState::new_seeded(2, &Deck::example(), 2);
+        game.emit(GameEvent::PassedMain { player: 1, hand_size: 7, untapped_mana: 5, land_drop: true });
+        assert!(game.belief(1, 1).hand_weight.values().all(|&w| w == 1.0));
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use rand::Rng;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use serde::{Serialize, Deserialize};
+
+use crate::card::{Card, CardType, Deck, Rarity};
+use crate::database::{CardDatabase, CardDatabaseError, CardDefinition};
+use crate::validator::is_basic_land;
+
+/// One group of cards in a pack, each drawn with the given rarity weights.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BoosterSlot
+{
+    pub count: usize,
+    pub weights: BTreeMap<Rarity, f64>,
+}
+
+/// How packs of a set are put together. Loads from TOML:
+///
+/// ```toml
+/// set = "M10"
+/// foil_rate = 0.167
+///
+/// [[slot]]
+/// count = 1
+/// weights = { rare = 7.0, mythic = 1.0 }
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Collation
+{
+    #[serde(default)]
+    pub set: Option<String>,
+    #[serde(rename = "slot")]
+    pub slots: Vec<BoosterSlot>,
+    #[serde(default)]
+    pub foil_rate: f64,
+    #[serde(default = "default_foil_weights")]
+    pub foil_weights: BTreeMap<Rarity, f64>,
+}
+
+fn default_foil_weights() -> BTreeMap<Rarity, f64>
+{
+    BTreeMap::from([(Rarity::Common, 10.0), (Rarity::Uncommon, 3.0), (Rarity::Rare, 0.875), (Rarity::Mythic, 0.125)])
+}
+
+impl Collation
+{
+    /// 15-card pack: a rare (mythic one time in eight), three uncommons and eleven commons.
+    pub fn standard(set: Option<&str>) -> Self
+    {
+        Collation
+        {
+            set: set.map(str::to_string),
+            slots: vec![
+                BoosterSlot { count: 1, weights: BTreeMap::from([(Rarity::Rare, 7.0), (Rarity::Mythic, 1.0)]) },
+                BoosterSlot { count: 3, weights: BTreeMap::from([(Rarity::Uncommon, 1.0)]) },
+                BoosterSlot { count: 11, weights: BTreeMap::from([(Rarity::Common, 1.0)]) },
+            ],
+            foil_rate: 1.0 / 6.0,
+            foil_weights: default_foil_weights(),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CardDatabaseError>
+    {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| CardDatabaseError::Io { path: path.to_path_buf(), error })?;
+        toml::from_str(&text)
+            .map_err(|e| CardDatabaseError::Parse { path: path.to_path_buf(), message: e.to_string() })
+    }
+
+    pub fn pack_size(&self) -> usize
+    {
+        self.slots.iter().map(|s| s.count).sum()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PackCard
+{
+    pub card: Card,
+    pub foil: bool,
+}
+
+/// Opens packs from the non-basic cards of a database, grouped by rarity.
+pub struct BoosterGenerator<'a>
+{
+    pub collation: Collation,
+    pools: BTreeMap<Rarity, Vec<&'a CardDefinition>>,
+}
+
+impl<'a> BoosterGenerator<'a>
+{
+    pub fn new(db: &'a CardDatabase, collation: Collation) -> Self
+    {
+        let mut pools: BTreeMap<Rarity, Vec<&CardDefinition>> = BTreeMap::new();
+        let candidates = match &collation.set
+        {
+            Some(set) => db.in_set(set),
+            None => db.filter(|_| true),
+        };
+        for definition in candidates.into_iter().filter(|d| !is_basic_land(&d.name))
+        {
+            if let Some(rarity) = definition.rarity
+            {
+                pools.entry(rarity).or_default().push(definition);
+            }
+        }
+        BoosterGenerator { collation, pools }
+    }
+
+    pub fn pool_size(&self, rarity: Rarity) -> usize
+    {
+        self.pools.get(&rarity).map_or(0, |p| p.len())
+    }
+
+    /// Open one pack. Cards don't repeat within a pack unless a rarity runs out;
+    /// a foil replaces a card from the last slot.
+    pub fn open_pack(&self, rng: &mut StdRng) -> Vec<PackCard>
+    {
+        let mut pack: Vec<PackCard> = Vec::new();
+
+        for slot in &self.collation.slots
+        {
+            for _ in 0..slot.count
+            {
+                if let Some(card) = self.draw(&slot.weights, &pack, rng)
+                {
+                    pack.push(PackCard { card, foil: false });
+                }
+            }
+        }
+
+        if !pack.is_empty() && rng.gen_bool(self.collation.foil_rate.clamp(0.0, 1.0))
+            && let Some(card) = self.draw(&self.collation.foil_weights, &[], rng)
+        {
+            let last = pack.len() - 1;
+            pack[last] = PackCard { card, foil: true };
+        }
+        pack
+    }
+
+    pub fn open_packs(&self, count: usize, rng: &mut StdRng) -> Vec<Vec<PackCard>>
+    {
+        (0..count).map(|_| self.open_pack(rng)).collect()
+    }
+
+    fn draw(&self, weights: &BTreeMap<Rarity, f64>, pack: &[PackCard], rng: &mut StdRng) -> Option<Card>
+    {
+        // Rarities with nothing to draw from drop out of the roll
+        let choices: Vec<(Rarity, f64)> = weights.iter()
+            .filter(|(r, w)| **w > 0.0 && self.pool_size(**r) > 0)
+            .map(|(r, w)| (*r, *w))
+            .collect();
+        let index = WeightedIndex::new(choices.iter().map(|(_, w)| *w)).ok()?;
+        let pool = &self.pools[&choices[index.sample(rng)].0];
+
+        let unused: Vec<&&CardDefinition> = pool.iter()
+            .filter(|d| !pack.iter().any(|p| p.card.name == d.name))
+            .collect();
+        let definition = if unused.is_empty()
+        {
+            pool[rng.gen_range(0..pool.len())]
+        }
+        else
+        {
+            unused[rng.gen_range(0..unused.len())]
+        };
+        Some(definition.to_card())
+    }
+}
+
+/// Rough pick order for bots and deck building: efficient creatures first,
+/// rarity only breaks ties.
+pub fn card_rating(card: &Card) -> f64
+{
+    let rarity_bonus = card.rarity.map_or(0.0, |r| match r
+    {
+        Rarity::Common => 0.0,
+        Rarity::Uncommon => 0.1,
+        Rarity::Rare | Rarity::Special | Rarity::Bonus => 0.2,
+        Rarity::Mythic => 0.3,
+    });
+
+    let body = match crate::creature::creature_stats(card)
+    {
+        Some(stats) => (stats.power as f64 + stats.toughness as f64) / card.cost.max(1) as f64,
+        None if card.is_type(CardType::Land) => 0.5,
+        None => 1.0,
+    };
+    body + 0.5 * (card.keywords.len() + card.abilities.len()) as f64 + rarity_bonus
+}
+
+/// Open `packs` packs into a sealed pool.
+pub fn sealed_pool(generator: &BoosterGenerator, packs: usize, rng: &mut StdRng) -> Vec<Card>
+{
+    generator.open_packs(packs, rng).into_iter().flatten().map(|p| p.card).collect()
+}
+
+/// Build a 40-card deck from a limited pool: the best 23 spells, then lands,
+/// topped up with `basic_land`. The rest of the pool is the sideboard.
+pub fn build_limited_deck(pool: &[Card], basic_land: &Card) -> Deck
+{
+    const DECK_SIZE: usize = 40;
+    const SPELLS: usize = 23;
+
+    let (mut lands, mut spells): (Vec<Card>, Vec<Card>) = pool.iter().cloned().partition(|c| c.is_type(CardType::Land));
+    spells.sort_by(|a, b| card_rating(b).total_cmp(&card_rating(a)));
+    lands.sort_by(|a, b| card_rating(b).total_cmp(&card_rating(a)));
+
+    let mut sideboard = spells.split_off(SPELLS.min(spells.len()));
+    let land_slots = DECK_SIZE - spells.len();
+    sideboard.extend(lands.split_off(land_slots.min(lands.len())));
+
+    let mut cards = spells;
+    cards.extend(lands);
+    while cards.len() < DECK_SIZE
+    {
+        cards.push(basic_land.clone());
+    }
+
+    Deck { cards, sideboard }
+}
+
+/// Chooses a card from the pack in front of a drafter.
+pub trait DraftPicker
+{
+    /// Index into `pack` of the card `seat` takes, given what they've already picked.
+    fn pick(&mut self, seat: usize, pack: &[PackCard], picked: &[Card]) -> usize;
+}
+
+/// Takes the highest rated card.
+pub struct BotPicker;
+
+impl DraftPicker for BotPicker
+{
+    fn pick(&mut self, _seat: usize, pack: &[PackCard], _picked: &[Card]) -> usize
+    {
+        pack.iter().enumerate()
+            .max_by(|(_, a), (_, b)| card_rating(&a.card).total_cmp(&card_rating(&b.card)))
+            .map_or(0, |(i, _)| i)
+    }
+}
+
+/// Booster draft: every seat opens a pack per round and packs pass left,
+/// then right, then left again until they're empty.
+pub struct Draft
+{
+    pub seats: usize,
+    pub rounds: usize,
+}
+
+impl Default for Draft
+{
+    fn default() -> Self
+    {
+        Draft { seats: 8, rounds: 3 }
+    }
+}
+
+impl Draft
+{
+    /// Run the draft and return every seat's picks.
+    pub fn run(&self, generator: &BoosterGenerator, pickers: &mut [&mut dyn DraftPicker], rng: &mut StdRng) -> Vec<Vec<Card>>
+    {
+        assert_eq!(pickers.len(), self.seats, "one picker per seat");
+        let mut picks: Vec<Vec<Card>> = vec![Vec::new(); self.seats];
+
+        for round in 0..self.rounds
+        {
+            let mut packs = generator.open_packs(self.seats, rng);
+            let pass_left = round % 2 == 0;
+
+            while packs.iter().any(|p| !p.is_empty())
+            {
+                for (seat, pack) in packs.iter_mut().enumerate()
+                {
+                    if pack.is_empty()
+                    {
+                        continue;
+                    }
+                    let index = pickers[seat].pick(seat, pack, &picks[seat]).min(pack.len() - 1);
+                    picks[seat].push(pack.remove(index).card);
+                }
+
+                if pass_left
+                {
+                    packs.rotate_right(1);
+                }
+                else
+                {
+                    packs.rotate_left(1);
+                }
+            }
+        }
+        picks
+    }
+
+    /// Draft with bots in every seat and build each seat a deck.
+    pub fn bot_decks(&self, generator: &BoosterGenerator, basic_land: &Card, rng: &mut StdRng) -> Vec<Deck>
+    {
+        let mut bots: Vec<BotPicker> = (0..self.seats).map(|_| BotPicker).collect();
+        let mut pickers: Vec<&mut dyn DraftPicker> = bots.iter_mut().map(|b| b as &mut dyn DraftPicker).collect();
+        self.run(generator, &mut pickers, rng)
+            .iter()
+            .map(|pool| build_limited_deck(pool, basic_land))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use rand::SeedableRng;
+
+    fn test_db() -> CardDatabase
+    {
+        let mut db = CardDatabase::new();
+        let rarities = [(Rarity::Common, 20), (Rarity::Uncommon, 8), (Rarity::Rare, 4), (Rarity::Mythic, 1)];
+        for (rarity, count) in rarities
+        {
+            for i in 0..count
+            {
+                db.insert(CardDefinition
+                {
+                    name: format!("{:?} Bear {}", rarity, i),
+                    types: vec![CardType::Creature],
+                    cost: 2,
+                    power: Some(2),
+                    toughness: Some(2),
+                    set: Some(String::from("TST")),
+                    rarity: Some(rarity),
+                    ..Default::default()
+                });
+            }
+        }
+        db
+    }
+
+    #[test]
+    fn packs_follow_collation()
+    {
+        let db = test_db();
+        let mut collation = Collation::standard(Some("TST"));
+        collation.foil_rate = 0.0;
+        let generator = BoosterGenerator::new(&db, collation);
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let pack = generator.open_pack(&mut rng);
+        assert_eq!(pack.len(), 15);
+        let count = |r: Rarity| pack.iter().filter(|p| p.card.rarity == Some(r)).count();
+        assert_eq!(count(Rarity::Rare) + count(Rarity::Mythic), 1);
+        assert_eq!(count(Rarity::Uncommon), 3);
+        assert_eq!(count(Rarity::Common), 11);
+    }
+
+    #[test]
+    fn collation_loads_from_toml()
+    {
+        let collation: Collation = toml::from_str(r#"
+            foil_rate = 1.0
+
+            [[slot]]
+            count = 2
+            weights = { uncommon = 1.0 }
+        "#).unwrap();
+        assert_eq!(collation.pack_size(), 2);
+
+        let db = test_db();
+        let generator = BoosterGenerator::new(&db, collation);
+        let pack = generator.open_pack(&mut StdRng::seed_from_u64(1));
+        assert!(pack.last().unwrap().foil);
+    }
+
+    #[test]
+    fn bot_draft_builds_forty_card_decks()
+    {
+        let db = test_db();
+        let generator = BoosterGenerator::new(&db, Collation::standard(None));
+        let decks = Draft::default().bot_decks(&generator, &crate::card::forest(), &mut StdRng::seed_from_u64(9));
+
+        assert_eq!(decks.len(), 8);
+        for deck in &decks
+        {
+            assert_eq!(deck.cards.len(), 40);
+            assert_eq!(deck.sideboard.len(), 45 - 23);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::any::Any;
 use serde::{Serialize, Deserialize};
 
@@ -9,6 +9,38 @@ pub enum CardType
     Creature,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rarity
+{
+    Common,
+    Uncommon,
+    Rare,
+    Mythic,
+    Special,
+    Bonus,
+}
+
+impl Rarity
+{
+    pub const ALL: [Rarity; 6] = [Rarity::Common, Rarity::Uncommon, Rarity::Rare, Rarity::Mythic, Rarity::Special, Rarity::Bonus];
+
+    /// Parse a rarity as written in data files and Scryfall ("common", "mythic", ...).
+    pub fn from_name(name: &str) -> Option<Rarity>
+    {
+        match name.to_ascii_lowercase().as_str()
+        {
+            "common" => Some(Rarity::Common),
+            "uncommon" => Some(Rarity::Uncommon),
+            "rare" => Some(Rarity::Rare),
+            "mythic" | "mythic rare" => Some(Rarity::Mythic),
+            "special" => Some(Rarity::Special),
+            "bonus" => Some(Rarity::Bonus),
+            _ => None,
+        }
+    }
+}
+
 // Use composition so only creatures have power/toughness.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct CreatureStats
@@ -17,11 +49,68 @@ pub struct CreatureStats
     pub toughness: u8,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+// Serialized as its tag so fragment maps keep string keys.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum CardFragmentKind
 {
     Creature,
     Tappable,
+    Exiled,
+    ExileLink,
+    Madness,
+    Abilities,
+    Custom(&'static str),   // registered with `registry::register_fragment`
+}
+
+impl CardFragmentKind
+{
+    pub const BUILT_IN: [CardFragmentKind; 6] = [
+        CardFragmentKind::Creature,
+        CardFragmentKind::Tappable,
+        CardFragmentKind::Exiled,
+        CardFragmentKind::ExileLink,
+        CardFragmentKind::Madness,
+        CardFragmentKind::Abilities,
+    ];
+
+    pub fn tag(&self) -> &'static str
+    {
+        match self
+        {
+            CardFragmentKind::Creature => "Creature",
+            CardFragmentKind::Tappable => "Tappable",
+            CardFragmentKind::Exiled => "Exiled",
+            CardFragmentKind::ExileLink => "ExileLink",
+            CardFragmentKind::Madness => "Madness",
+            CardFragmentKind::Abilities => "Abilities",
+            CardFragmentKind::Custom(tag) => tag,
+        }
+    }
+
+    /// Look up a kind by tag, including registered custom kinds.
+    pub fn from_tag(tag: &str) -> Option<CardFragmentKind>
+    {
+        Self::BUILT_IN.iter().copied().find(|k| k.tag() == tag)
+            .or_else(|| crate::registry::registered_tag(tag).map(CardFragmentKind::Custom))
+    }
+}
+
+impl Serialize for CardFragmentKind
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        serializer.serialize_str(self.tag())
+    }
+}
+
+impl<'de> Deserialize<'de> for CardFragmentKind
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        let tag = String::deserialize(deserializer)?;
+        CardFragmentKind::from_tag(&tag)
+            .ok_or_else(|| serde::de::Error::custom(format!("unregistered fragment kind \"{}\"", tag)))
+    }
 }
 
 pub trait Fragment: Any + Send + Sync
@@ -74,6 +163,78 @@ impl Fragment for TappableFragment
     }
 }
 
+impl Fragment for ExiledFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+}
+
+impl Fragment for ExileLinkFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+}
+
+impl Fragment for MadnessFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+}
+
+impl Fragment for AbilitiesFragment
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any
+    {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn Fragment>
+    {
+        Box::new(self.clone())
+    }
+}
+
 impl Clone for Box<dyn Fragment>
 {
     fn clone(&self) -> Box<dyn Fragment>
@@ -88,21 +249,31 @@ pub enum SerializableFragment
 {
     Creature(CreatureFragment),
     Tappable(TappableFragment),
+    Exiled(ExiledFragment),
+    ExileLink(ExileLinkFragment),
+    Madness(MadnessFragment),
+    Abilities(AbilitiesFragment),
+    Custom { tag: String, data: serde_json::Value },
 }
 
 impl SerializableFragment
 {
-    /// Convert to trait object
-    pub fn to_fragment(&self) -> Box<dyn Fragment>
+    /// Convert to trait object. Fails for custom fragments whose tag isn't registered.
+    pub fn to_fragment(&self) -> Result<Box<dyn Fragment>, String>
     {
         match self
         {
-            SerializableFragment::Creature(cf) => Box::new(cf.clone()),
-            SerializableFragment::Tappable(tf) => Box::new(tf.clone()),
+            SerializableFragment::Creature(cf) => Ok(Box::new(cf.clone())),
+            SerializableFragment::Tappable(tf) => Ok(Box::new(tf.clone())),
+            SerializableFragment::Exiled(ef) => Ok(Box::new(ef.clone())),
+            SerializableFragment::ExileLink(lf) => Ok(Box::new(lf.clone())),
+            SerializableFragment::Madness(mf) => Ok(Box::new(mf.clone())),
+            SerializableFragment::Abilities(af) => Ok(Box::new(af.clone())),
+            SerializableFragment::Custom { tag, data } => crate::registry::deserialize_custom(tag, data.clone()),
         }
     }
 
-    /// Convert from trait object (best effort)
+    /// Convert from trait object. None if the fragment type isn't built in or registered.
     pub fn from_fragment(fragment: &dyn Fragment) -> Option<Self>
     {
         if let Some(cf) = fragment.as_any().downcast_ref::<CreatureFragment>()
@@ -113,7 +284,24 @@ impl SerializableFragment
         {
             return Some(SerializableFragment::Tappable(tf.clone()));
         }
-        None
+        if let Some(ef) = fragment.as_any().downcast_ref::<ExiledFragment>()
+        {
+            return Some(SerializableFragment::Exiled(ef.clone()));
+        }
+        if let Some(lf) = fragment.as_any().downcast_ref::<ExileLinkFragment>()
+        {
+            return Some(SerializableFragment::ExileLink(lf.clone()));
+        }
+        if let Some(mf) = fragment.as_any().downcast_ref::<MadnessFragment>()
+        {
+            return Some(SerializableFragment::Madness(mf.clone()));
+        }
+        if let Some(af) = fragment.as_any().downcast_ref::<AbilitiesFragment>()
+        {
+            return Some(SerializableFragment::Abilities(af.clone()));
+        }
+        crate::registry::serialize_custom(fragment)
+            .map(|(tag, data)| SerializableFragment::Custom { tag: tag.to_string(), data })
     }
 }
 
@@ -123,12 +311,59 @@ pub struct TappableFragment
     pub tapped: bool,
 }
 
+// State of a card sitting in exile.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExiledFragment
+{
+    pub face_down: bool,
+    pub playable_by: Option<usize>,         // player index allowed to play it from exile
+    pub playable_until_turn: Option<u32>,   // None means for as long as it stays exiled
+    pub linked_to: Option<u32>,             // returns when the source with this link leaves
+    #[serde(default)]
+    pub madness: bool,                      // discarded with madness; goes to the graveyard if not cast in time
+}
+
+// Marks a permanent that holds cards in exile "until it leaves the battlefield".
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExileLinkFragment
+{
+    pub link_id: u32,
+}
+
+// When discarded, the card is exiled instead and may be cast this turn for `cost`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MadnessFragment
+{
+    pub cost: u32,
+}
+
+// Parsed from the card's ability text, see `ability::parse_ability`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AbilitiesFragment
+{
+    pub abilities: Vec<crate::ability::Ability>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Card
 {
     pub name: String,
     pub card_types: Vec<CardType>,
     pub cost: u32,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub abilities: Vec<String>,   // rules text, one ability per entry
+    #[serde(default)]
+    pub set_code: Option<String>,
+    #[serde(default)]
+    pub collector_number: Option<String>,
+    #[serde(default)]
+    pub rarity: Option<Rarity>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub flavor_text: Option<String>,
     #[serde(serialize_with = "serialize_fragments", deserialize_with = "deserialize_fragments")]
     pub fragments: HashMap<CardFragmentKind, Box<dyn Fragment>>,
 }
@@ -141,12 +376,14 @@ fn serialize_fragments<S>(
 where
     S: serde::Serializer,
 {
-    let serializable: HashMap<CardFragmentKind, SerializableFragment> = fragments
-        .iter()
-        .filter_map(|(k, v)| {
-            SerializableFragment::from_fragment(v.as_ref()).map(|sf| (*k, sf))
-        })
-        .collect();
+    // Refuse to drop fragments we don't know how to write
+    let mut serializable: HashMap<CardFragmentKind, SerializableFragment> = HashMap::new();
+    for (k, v) in fragments
+    {
+        let sf = SerializableFragment::from_fragment(v.as_ref()).ok_or_else(||
+            serde::ser::Error::custom(format!("fragment \"{}\" has no registered serializer", k.tag())))?;
+        serializable.insert(*k, sf);
+    }
     serializable.serialize(serializer)
 }
 
@@ -159,10 +396,10 @@ where
 {
     let serializable: HashMap<CardFragmentKind, SerializableFragment> =
         HashMap::deserialize(deserializer)?;
-    Ok(serializable
+    serializable
         .into_iter()
-        .map(|(k, v)| (k, v.to_fragment()))
-        .collect())
+        .map(|(k, v)| v.to_fragment().map(|f| (k, f)).map_err(serde::de::Error::custom))
+        .collect()
 }
 
 impl std::fmt::Debug for Card
@@ -173,12 +410,19 @@ impl std::fmt::Debug for Card
             .field("name", &self.name)
             .field("card_types", &self.card_types)
             .field("cost", &self.cost)
+            .field("keywords", &self.keywords)
             .finish()
     }
 }
 
 impl Card
 {
+    /// Set codes compare case-insensitively ("M10" and "m10" are the same set).
+    pub fn is_from_set(&self, set_code: &str) -> bool
+    {
+        self.set_code.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(set_code))
+    }
+
     pub fn is_type(&self, t: CardType) -> bool
     {
         self.card_types.iter().any(|ct| *ct == t)
@@ -192,6 +436,11 @@ impl Card
         }
     }
 
+    pub fn has_keyword(&self, keyword: &str) -> bool
+    {
+        self.keywords.iter().any(|k| k.eq_ignore_ascii_case(keyword))
+    }
+
     pub fn remove_type(&mut self, t: CardType)
     {
         if let Some(pos) = self.card_types.iter().position(|ct| *ct == t)
@@ -201,10 +450,21 @@ impl Card
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Deck
 {
     pub cards: Vec<Card>,
+    pub sideboard: Vec<Card>,
+}
+
+pub fn rarity_counts<'a>(cards: impl IntoIterator<Item = &'a Card>) -> BTreeMap<Rarity, usize>
+{
+    let mut counts = BTreeMap::new();
+    for rarity in cards.into_iter().filter_map(|c| c.rarity)
+    {
+        *counts.entry(rarity).or_insert(0) += 1;
+    }
+    counts
 }
 
 impl Deck
@@ -214,6 +474,22 @@ impl Deck
         self.cards.iter().filter(|c| c.is_type(card_type)).count()
     }
 
+    /// How many main deck cards there are of each rarity. Cards without a rarity are left out.
+    pub fn rarity_breakdown(&self) -> BTreeMap<Rarity, usize>
+    {
+        rarity_counts(&self.cards)
+    }
+
+    pub fn cards_of_rarity(&self, rarity: Rarity) -> impl Iterator<Item = &Card>
+    {
+        self.cards.iter().filter(move |c| c.rarity == Some(rarity))
+    }
+
+    pub fn cards_from_set<'a>(&'a self, set_code: &'a str) -> impl Iterator<Item = &'a Card>
+    {
+        self.cards.iter().filter(move |c| c.is_from_set(set_code))
+    }
+
     pub fn example() -> Deck
     {
         let mut cards = Vec::new();
@@ -225,7 +501,7 @@ impl Deck
             cards.push(grizzly_bears());
         }
 
-        Deck { cards }
+        Deck { cards, sideboard: Vec::new() }
     }
 }
 
@@ -236,6 +512,13 @@ pub fn forest() -> Card
         name: String::from("Forest"),
         card_types: vec![CardType::Land],
         cost: 0,
+        keywords: Vec::new(),
+        abilities: Vec::new(),
+        set_code: None,
+        collector_number: None,
+        rarity: None,
+        artist: None,
+        flavor_text: None,
         fragments: {
             let mut m = HashMap::new();
             m.insert(
@@ -254,6 +537,13 @@ pub fn grizzly_bears() -> Card
         name: String::from("Grizzly Bears"),
         card_types: vec![CardType::Creature],
         cost: 2,
+        keywords: Vec::new(),
+        abilities: Vec::new(),
+        set_code: None,
+        collector_number: None,
+        rarity: None,
+        artist: None,
+        flavor_text: None,
         fragments: {
             let mut m = HashMap::new();
             m.insert(
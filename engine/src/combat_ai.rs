@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use crate::card::Card;
+use crate::controller::{can_attack, can_block};
+use crate::game::{GameState, Zone};
+
+// Combat heuristics. Creatures only have power and toughness, so a block
+// either kills the attacker, kills the blocker, both, or neither.
+
+fn stats(card: &Card) -> (i32, i32)
+{
+    crate::creature::creature_stats(card)
+        .map(|s| (s.power as i32, s.toughness as i32))
+        .unwrap_or((0, 0))
+}
+
+/// How much we care about losing a creature.
+pub fn creature_value(card: &Card) -> i32
+{
+    let (power, toughness) = stats(card);
+    power + toughness
+}
+
+/// Turns `attack_power` per turn needs to take `life` to zero; None if it never will.
+pub fn clock(life: i32, attack_power: i32) -> Option<i32>
+{
+    if life <= 0
+    {
+        return Some(0);
+    }
+    (attack_power > 0).then(|| (life + attack_power - 1) / attack_power)
+}
+
+/// Damage that gets through if the defender uses each blocker to stop the
+/// biggest remaining attacker.
+pub fn unblocked_damage(mut attack_powers: Vec<i32>, blockers: usize) -> i32
+{
+    attack_powers.sort_unstable_by(|a, b| b.cmp(a));
+    attack_powers.iter().skip(blockers).sum()
+}
+
+/// Pick attackers for `player`: everything when it's lethal through any
+/// blocks, otherwise creatures that can't be blocked profitably, keeping
+/// enough back to survive the crack-back.
+pub fn choose_attackers(game: &GameState, player: usize) -> Vec<usize>
+{
+    let defender = (player + 1) % game.players.len();
+    let mine = &game.players[player].zones[&Zone::Battlefield];
+    let theirs = &game.players[defender].zones[&Zone::Battlefield];
+
+    let candidates: Vec<usize> = mine.iter().enumerate()
+        .filter(|(_, c)| can_attack(c) && stats(c).0 > 0)
+        .map(|(i, _)| i)
+        .collect();
+    if candidates.is_empty()
+    {
+        return Vec::new();
+    }
+
+    let blockers: Vec<&Card> = theirs.iter().filter(|c| can_block(c)).collect();
+    let their_life = game.players[defender].life;
+    let my_life = game.players[player].life;
+
+    // Alpha strike if they can't block enough of it
+    let powers: Vec<i32> = candidates.iter().map(|&i| stats(&mine[i]).0).collect();
+    if unblocked_damage(powers, blockers.len()) >= their_life
+    {
+        return candidates;
+    }
+
+    // Race: compare how fast each side kills the other with everything
+    let my_power: i32 = mine.iter().filter(|c| crate::creature::is_creature(c)).map(|c| stats(c).0).sum();
+    let their_power: i32 = theirs.iter().filter(|c| crate::creature::is_creature(c)).map(|c| stats(c).0).sum();
+    let winning_race = match (clock(their_life, my_power), clock(my_life, their_power))
+    {
+        (Some(mine), Some(theirs)) => mine <= theirs,
+        (Some(_), None) => true,
+        _ => false,
+    };
+
+    let mut attackers: Vec<usize> = candidates.into_iter()
+        .filter(|&i|
+        {
+            let (power, toughness) = stats(&mine[i]);
+            let eaten = blockers.iter().any(|b| { let (bp, bt) = stats(b); bp >= toughness && power < bt });
+            let traded = blockers.iter().any(|b| { let (bp, bt) = stats(b); bp >= toughness && power >= bt });
+            !eaten && (!traded || winning_race)
+        })
+        .collect();
+
+    // Hold back blockers until their counterattack next turn isn't lethal
+    let their_attack: Vec<i32> = theirs.iter().filter(|c| crate::creature::is_creature(c)).map(|c| stats(c).0).collect();
+    let defenders_left = |attackers: &[usize]| mine.iter().enumerate()
+        .filter(|(i, c)| crate::creature::is_creature(c) && !attackers.contains(i))
+        .count();
+    attackers.sort_by_key(|&i| creature_value(&mine[i]));
+    while !attackers.is_empty() && unblocked_damage(their_attack.clone(), defenders_left(&attackers)) >= my_life
+    {
+        attackers.pop();
+    }
+    attackers.sort_unstable();
+    attackers
+}
+
+/// Assign blocks for `player` against `attackers` (indices on the attacking
+/// player's battlefield): eat attackers when a blocker survives, trade when
+/// it's worth it, and chump only to avoid lethal damage.
+pub fn choose_blocks(game: &GameState, player: usize, attackers: &[usize]) -> HashMap<usize, usize>
+{
+    let attacking_player = game.current_player_index;
+    let their_board = &game.players[attacking_player].zones[&Zone::Battlefield];
+    let my_board = &game.players[player].zones[&Zone::Battlefield];
+    let life = game.players[player].life;
+
+    let mut incoming: Vec<usize> = attackers.iter().copied().filter(|&a| a < their_board.len()).collect();
+    incoming.sort_by_key(|&a| -stats(&their_board[a]).0);
+    let mut available: Vec<usize> = my_board.iter().enumerate()
+        .filter(|(_, c)| can_block(c))
+        .map(|(i, _)| i)
+        .collect();
+    let mut blocks = HashMap::new();
+
+    let total: i32 = incoming.iter().map(|&a| stats(&their_board[a]).0).sum();
+    let facing_lethal = total >= life;
+
+    for &attacker in &incoming
+    {
+        let (power, toughness) = stats(&their_board[attacker]);
+        let kills = |b: usize| stats(&my_board[b]).0 >= toughness;
+        let survives = |b: usize| stats(&my_board[b]).1 > power;
+
+        // Cheapest blocker that kills it and lives, then a worthwhile trade
+        let eat = available.iter().copied()
+            .filter(|&b| kills(b) && survives(b))
+            .min_by_key(|&b| creature_value(&my_board[b]));
+        let trade = || available.iter().copied()
+            .filter(|&b| kills(b) && (facing_lethal || creature_value(&my_board[b]) <= creature_value(&their_board[attacker])))
+            .min_by_key(|&b| creature_value(&my_board[b]));
+        // A blocker that survives without killing still stops the damage
+        let wall = || available.iter().copied().find(|&b| survives(b));
+
+        if let Some(b) = eat.or_else(trade).or_else(wall)
+        {
+            blocks.insert(b, attacker);
+            available.retain(|&x| x != b);
+        }
+    }
+
+    // Still lethal: chump the biggest unblocked attackers with the least valuable creatures
+    let unblocked = |blocks: &HashMap<usize, usize>| incoming.iter()
+        .filter(|a| !blocks.values().any(|x| x == *a))
+        .map(|&a| stats(&their_board[a]).0)
+        .sum::<i32>();
+    available.sort_by_key(|&b| creature_value(&my_board[b]));
+    for &attacker in &incoming
+    {
+        if unblocked(&blocks) < life || available.is_empty()
+        {
+            break;
+        }
+        if !blocks.values().any(|&a| a == attacker)
+        {
+            blocks.insert(available.remove(0), attacker);
+        }
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{grizzly_bears, Deck};
+    use crate::creature::add_creature_fragment;
+
+    fn creature(power: u8, toughness: u8) -> Card
+    {
+        let mut card = grizzly_bears();
+        add_creature_fragment(&mut card, power, toughness);
+        card
+    }
+
+    fn board(mine: Vec<Card>, theirs: Vec<Card>) -> GameState
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 4);
+        gs.players[0].zones.insert(Zone::Battlefield, mine);
+        gs.players[1].zones.insert(Zone::Battlefield, theirs);
+        gs
+    }
+
+    #[test]
+    fn attacks_around_a_bigger_blocker_only_when_safe()
+    {
+        let gs = board(vec![creature(2, 2), creature(5, 5)], vec![creature(3, 3)]);
+        assert_eq!(choose_attackers(&gs, 0), vec![1]);
+    }
+
+    #[test]
+    fn alpha_strikes_for_lethal()
+    {
+        let mut gs = board(vec![creature(2, 2), creature(2, 2), creature(2, 2)], vec![creature(4, 4)]);
+        gs.players[1].life = 4;
+        assert_eq!(choose_attackers(&gs, 0), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn blocks_profitably_and_chumps_only_against_lethal()
+    {
+        // Player 0 attacks with a 3/3 and a 6/6 into a 4/4 and a 1/1
+        let gs = board(vec![creature(3, 3), creature(6, 6)], vec![creature(4, 4), creature(1, 1)]);
+        let blocks = choose_blocks(&gs, 1, &[0, 1]);
+        assert_eq!(blocks.get(&0), Some(&0), "the 4/4 eats the 3/3");
+        assert_eq!(blocks.get(&1), None, "no chump block when the attack isn't lethal");
+
+        let mut gs = board(vec![creature(5, 5)], vec![creature(1, 1)]);
+        gs.players[1].life = 5;
+        assert_eq!(choose_blocks(&gs, 1, &[0]).get(&0), Some(&0), "chump when the hit is lethal");
+    }
+}
@@ -0,0 +1,579 @@
+use std::collections::{HashMap, VecDeque};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::ability::Target;
+use crate::action::Action;
+use crate::card::{Card, CardType};
+use crate::game::{GameState, Zone};
+
+/// Makes every choice for one seat. The engine asks the controller of the
+/// player the decision belongs to and then checks that the answer is legal.
+pub trait PlayerController: Send + Sync
+{
+    fn name(&self) -> &str;
+
+    /// Keep the opening hand? `mulligans` is how many have been taken so far.
+    fn keep_hand(&mut self, game: &GameState, player: usize, mulligans: u32) -> bool;
+
+    /// After keeping a mulliganed hand, pick `count` hand indices to put on the bottom.
+    fn cards_to_bottom(&mut self, game: &GameState, player: usize, count: usize) -> Vec<usize>;
+
+    /// Next land or spell to play in the main phase, or None to pass.
+    fn main_action(&mut self, game: &GameState, player: usize) -> Option<Action>;
+
+    /// Battlefield indices of the creatures to attack with.
+    fn declare_attackers(&mut self, game: &GameState, player: usize) -> Vec<usize>;
+
+    /// Blocker index -> attacker index, both into their controllers' battlefields.
+    fn declare_blockers(&mut self, game: &GameState, player: usize, attackers: &[usize]) -> HashMap<usize, usize>;
+
+    /// The player targeted by an effect `player` controls.
+    fn choose_player_target(&mut self, game: &GameState, player: usize, target: Target) -> usize;
+
+    /// Hand index to discard when `player` chooses what to discard.
+    fn choose_discard(&mut self, game: &GameState, player: usize) -> usize;
+
+    /// Scry `count`: for each card looked at, top card first, whether it
+    /// stays on top (false puts it on the bottom).
+    fn choose_scry(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>;
+
+    /// Surveil `count`: for each card looked at, top card first, whether it
+    /// stays on top (false puts it into the graveyard).
+    fn choose_surveil(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>;
+
+    /// Scripted decisions not yet used; only [`ScriptedController`] has any.
+    fn remaining(&self) -> usize
+    {
+        0
+    }
+
+    fn box_clone(&self) -> Box<dyn PlayerController>;
+}
+
+impl Clone for Box<dyn PlayerController>
+{
+    fn clone(&self) -> Box<dyn PlayerController>
+    {
+        self.box_clone()
+    }
+}
+
+impl std::fmt::Debug for Box<dyn PlayerController>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The original autopilot: keep every hand, play lands and the first castable
+/// creature, attack with everything and never block.
+#[derive(Clone, Debug, Default)]
+pub struct GreedyController;
+
+impl PlayerController for GreedyController
+{
+    fn name(&self) -> &str
+    {
+        "greedy"
+    }
+
+    fn keep_hand(&mut self, _game: &GameState, _player: usize, _mulligans: u32) -> bool
+    {
+        true
+    }
+
+    fn cards_to_bottom(&mut self, _game: &GameState, _player: usize, count: usize) -> Vec<usize>
+    {
+        (0..count).collect()
+    }
+
+    fn main_action(&mut self, game: &GameState, _player: usize) -> Option<Action>
+    {
+        game.autopilot_action()
+    }
+
+    fn declare_attackers(&mut self, game: &GameState, player: usize) -> Vec<usize>
+    {
+        game.players[player].zones[&Zone::Battlefield].iter().enumerate()
+            .filter(|(_, c)| can_attack(c))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn declare_blockers(&mut self, _game: &GameState, _player: usize, _attackers: &[usize]) -> HashMap<usize, usize>
+    {
+        HashMap::new()
+    }
+
+    fn choose_player_target(&mut self, game: &GameState, player: usize, _target: Target) -> usize
+    {
+        (player + 1) % game.players.len()
+    }
+
+    fn choose_discard(&mut self, game: &GameState, player: usize) -> usize
+    {
+        let zones = &game.players[player].zones;
+        let lands_in_play = zones[&Zone::Battlefield].iter().filter(|c| c.is_type(CardType::Land)).count() as u32;
+        crate::discard::autopilot_discard(&zones[&Zone::Hand], lands_in_play)
+    }
+
+    fn choose_scry(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        library_top(game, player, count).map(|card| wants_to_draw(game, player, card)).collect()
+    }
+
+    fn choose_surveil(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        self.choose_scry(game, player, count)
+    }
+
+    fn box_clone(&self) -> Box<dyn PlayerController>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// Plays like [`GreedyController`] but attacks and blocks with the combat
+/// heuristics in `combat_ai`. This is what seats use unless told otherwise.
+#[derive(Clone, Debug, Default)]
+pub struct HeuristicController;
+
+impl PlayerController for HeuristicController
+{
+    fn name(&self) -> &str
+    {
+        "heuristic"
+    }
+
+    fn keep_hand(&mut self, game: &GameState, player: usize, mulligans: u32) -> bool
+    {
+        GreedyController.keep_hand(game, player, mulligans)
+    }
+
+    fn cards_to_bottom(&mut self, game: &GameState, player: usize, count: usize) -> Vec<usize>
+    {
+        GreedyController.cards_to_bottom(game, player, count)
+    }
+
+    fn main_action(&mut self, game: &GameState, player: usize) -> Option<Action>
+    {
+        GreedyController.main_action(game, player)
+    }
+
+    fn declare_attackers(&mut self, game: &GameState, player: usize) -> Vec<usize>
+    {
+        crate::combat_ai::choose_attackers(game, player)
+    }
+
+    fn declare_blockers(&mut self, game: &GameState, player: usize, attackers: &[usize]) -> HashMap<usize, usize>
+    {
+        crate::combat_ai::choose_blocks(game, player, attackers)
+    }
+
+    fn choose_player_target(&mut self, game: &GameState, player: usize, target: Target) -> usize
+    {
+        GreedyController.choose_player_target(game, player, target)
+    }
+
+    fn choose_discard(&mut self, game: &GameState, player: usize) -> usize
+    {
+        GreedyController.choose_discard(game, player)
+    }
+
+    fn choose_scry(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        GreedyController.choose_scry(game, player, count)
+    }
+
+    fn choose_surveil(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        GreedyController.choose_surveil(game, player, count)
+    }
+
+    fn box_clone(&self) -> Box<dyn PlayerController>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// Picks uniformly at random among the legal options, e.g. to shake out
+/// rules bugs no sensible player would run into.
+#[derive(Clone, Debug)]
+pub struct RandomController
+{
+    rng: StdRng,
+}
+
+impl RandomController
+{
+    pub fn new(seed: u64) -> Self
+    {
+        RandomController { rng: StdRng::seed_from_u64(seed) }
+    }
+
+    fn pick(&mut self, game: &GameState, player: usize) -> Option<Action>
+    {
+        game.legal_actions(player).choose(&mut self.rng).cloned()
+    }
+}
+
+impl PlayerController for RandomController
+{
+    fn name(&self) -> &str
+    {
+        "random"
+    }
+
+    fn keep_hand(&mut self, _game: &GameState, _player: usize, _mulligans: u32) -> bool
+    {
+        self.rng.gen_bool(0.5)
+    }
+
+    fn cards_to_bottom(&mut self, game: &GameState, player: usize, count: usize) -> Vec<usize>
+    {
+        let hand_size = game.players[player].zones[&Zone::Hand].len();
+        rand::seq::index::sample(&mut self.rng, hand_size, count.min(hand_size)).into_vec()
+    }
+
+    fn main_action(&mut self, game: &GameState, player: usize) -> Option<Action>
+    {
+        self.pick(game, player).filter(|a| *a != Action::PassPriority)
+    }
+
+    fn declare_attackers(&mut self, game: &GameState, player: usize) -> Vec<usize>
+    {
+        match self.pick(game, player)
+        {
+            Some(Action::Attack(attackers)) => attackers,
+            _ => Vec::new(),
+        }
+    }
+
+    fn declare_blockers(&mut self, game: &GameState, player: usize, _attackers: &[usize]) -> HashMap<usize, usize>
+    {
+        match self.pick(game, player)
+        {
+            Some(Action::Block(blocks)) => blocks.into_iter().collect(),
+            _ => HashMap::new(),
+        }
+    }
+
+    fn choose_player_target(&mut self, game: &GameState, _player: usize, _target: Target) -> usize
+    {
+        self.rng.gen_range(0..game.players.len())
+    }
+
+    fn choose_discard(&mut self, game: &GameState, player: usize) -> usize
+    {
+        self.rng.gen_range(0..game.players[player].zones[&Zone::Hand].len().max(1))
+    }
+
+    fn choose_scry(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        library_top(game, player, count).map(|_| self.rng.gen_bool(0.5)).collect()
+    }
+
+    fn choose_surveil(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        self.choose_scry(game, player, count)
+    }
+
+    fn box_clone(&self) -> Box<dyn PlayerController>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// One scripted answer for [`ScriptedController`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Decision
+{
+    Keep(bool),
+    Bottom(Vec<usize>),
+    /// A main phase action, `Action::Attack` or `Action::Block`.
+    Act(Action),
+    Target(usize),
+    Discard(usize),
+    /// Scry or surveil: whether each card looked at stays on top, top card first.
+    KeepOnTop(Vec<bool>),
+}
+
+/// Replays a fixed list of decisions in order, for deterministic rules tests.
+/// A question the next decision doesn't answer gets the passive default
+/// (keep, pass, no attacks or blocks, target the next player, discard the
+/// first card, leave scried cards on top) and leaves the script where it is, so a main phase keeps
+/// taking scripted actions until it reaches a `PassPriority` or a combat
+/// decision. Panics when a scripted decision is illegal; tests should check
+/// [`PlayerController::remaining`] or call `assert_exhausted` at the end so a
+/// script written for the wrong step doesn't pass by doing nothing.
+#[derive(Clone, Debug)]
+pub struct ScriptedController
+{
+    script: VecDeque<Decision>,
+}
+
+impl ScriptedController
+{
+    pub fn new(decisions: impl IntoIterator<Item = Decision>) -> Self
+    {
+        ScriptedController { script: decisions.into_iter().collect() }
+    }
+
+    /// Panic if any decision was never asked for.
+    pub fn assert_exhausted(&self)
+    {
+        assert!(self.script.is_empty(), "scripted decisions never used: {:?}", self.script);
+    }
+
+    /// Take the next decision if `answers` says it fits the question being asked.
+    fn next_if(&mut self, answers: impl FnOnce(&Decision) -> bool) -> Option<Decision>
+    {
+        if self.script.front().is_some_and(answers)
+        {
+            self.script.pop_front()
+        }
+        else
+        {
+            None
+        }
+    }
+
+    /// The next scripted action for the current step, checked against a copy of the game.
+    fn next_action(&mut self, game: &GameState, player: usize, fits: impl FnOnce(&Action) -> bool) -> Option<Action>
+    {
+        let Some(Decision::Act(action)) = self.next_if(|d| matches!(d, Decision::Act(a) if fits(a)))
+        else
+        {
+            return None;
+        };
+
+        if let Err(error) = game.clone().apply_action(player, action.clone())
+        {
+            panic!("scripted {:?} for player {} is illegal during {:?}: {}", action, player + 1, game.step, error);
+        }
+        Some(action)
+    }
+}
+
+impl PlayerController for ScriptedController
+{
+    fn name(&self) -> &str
+    {
+        "scripted"
+    }
+
+    fn keep_hand(&mut self, _game: &GameState, _player: usize, _mulligans: u32) -> bool
+    {
+        match self.next_if(|d| matches!(d, Decision::Keep(_)))
+        {
+            Some(Decision::Keep(keep)) => keep,
+            _ => true,
+        }
+    }
+
+    fn cards_to_bottom(&mut self, game: &GameState, player: usize, count: usize) -> Vec<usize>
+    {
+        match self.next_if(|d| matches!(d, Decision::Bottom(_)))
+        {
+            Some(Decision::Bottom(indices)) =>
+            {
+                let hand_size = game.players[player].zones[&Zone::Hand].len();
+                if indices.len() != count || indices.iter().any(|&i| i >= hand_size)
+                {
+                    panic!("scripted bottom {:?} for player {} must be {} indices into a hand of {}", indices, player + 1, count, hand_size);
+                }
+                indices
+            }
+            _ => (0..count).collect(),
+        }
+    }
+
+    fn main_action(&mut self, game: &GameState, player: usize) -> Option<Action>
+    {
+        self.next_action(game, player, |a| !matches!(a, Action::Attack(_) | Action::Block(_)))
+            .filter(|a| *a != Action::PassPriority)
+    }
+
+    fn declare_attackers(&mut self, game: &GameState, player: usize) -> Vec<usize>
+    {
+        match self.next_action(game, player, |a| matches!(a, Action::Attack(_)))
+        {
+            Some(Action::Attack(attackers)) => attackers,
+            _ => Vec::new(),
+        }
+    }
+
+    fn declare_blockers(&mut self, game: &GameState, player: usize, _attackers: &[usize]) -> HashMap<usize, usize>
+    {
+        match self.next_action(game, player, |a| matches!(a, Action::Block(_)))
+        {
+            Some(Action::Block(blocks)) => blocks.into_iter().collect(),
+            _ => HashMap::new(),
+        }
+    }
+
+    fn choose_player_target(&mut self, game: &GameState, player: usize, _target: Target) -> usize
+    {
+        match self.next_if(|d| matches!(d, Decision::Target(_)))
+        {
+            Some(Decision::Target(target)) if target < game.players.len() => target,
+            Some(Decision::Target(target)) => panic!("scripted target {} for player {} is not a player", target, player + 1),
+            _ => (player + 1) % game.players.len(),
+        }
+    }
+
+    fn choose_discard(&mut self, game: &GameState, player: usize) -> usize
+    {
+        match self.next_if(|d| matches!(d, Decision::Discard(_)))
+        {
+            Some(Decision::Discard(index)) if index < game.players[player].zones[&Zone::Hand].len() => index,
+            Some(Decision::Discard(index)) => panic!("scripted discard {} for player {} is not in their hand", index, player + 1),
+            _ => 0,
+        }
+    }
+
+    fn choose_scry(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        let looked_at = library_top(game, player, count).count();
+        match self.next_if(|d| matches!(d, Decision::KeepOnTop(_)))
+        {
+            Some(Decision::KeepOnTop(keep)) if keep.len() == looked_at => keep,
+            Some(Decision::KeepOnTop(keep)) => panic!("scripted {:?} for player {} must answer for {} cards", keep, player + 1, looked_at),
+            _ => vec![true; looked_at],
+        }
+    }
+
+    fn choose_surveil(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        self.choose_scry(game, player, count)
+    }
+
+    fn remaining(&self) -> usize
+    {
+        self.script.len()
+    }
+
+    fn box_clone(&self) -> Box<dyn PlayerController>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// The controller a seat gets when nobody picked one.
+pub fn default_controller() -> Box<dyn PlayerController>
+{
+    Box::new(HeuristicController)
+}
+
+/// Untapped creatures that have been under their controller's control since the turn began.
+pub fn can_attack(card: &Card) -> bool
+{
+    crate::creature::is_creature(card)
+        && !crate::creature::has_summoning_sickness(card)
+        && !crate::tappable::is_tapped(card)
+}
+
+/// The top `count` cards of `player`'s library, top card first.
+pub fn library_top(game: &GameState, player: usize, count: usize) -> impl Iterator<Item = &Card>
+{
+    game.players[player].zones[&Zone::Library].iter().rev().take(count)
+}
+
+// Lands are worth drawing until there are enough of them in play and in hand
+const LANDS_WANTED: usize = 5;
+
+/// Whether `player` would rather draw `card` next than something unknown.
+pub fn wants_to_draw(game: &GameState, player: usize, card: &Card) -> bool
+{
+    let zones = &game.players[player].zones;
+    let lands = zones[&Zone::Battlefield].iter().chain(&zones[&Zone::Hand]).filter(|c| c.is_type(CardType::Land)).count();
+    if card.is_type(CardType::Land)
+    {
+        lands < LANDS_WANTED
+    }
+    else
+    {
+        card.cost as usize <= lands + 1
+    }
+}
+
+/// Untapped creatures can block.
+pub fn can_block(card: &Card) -> bool
+{
+    crate::creature::is_creature(card) && !crate::tappable::is_tapped(card)
+}
+
+impl GameState
+{
+    /// Seat `player` with `controller`. Seats without one use [`default_controller`].
+    pub fn set_controller(&mut self, player: usize, controller: Box<dyn PlayerController>)
+    {
+        if self.controllers.len() <= player
+        {
+            self.controllers.resize_with(player + 1, default_controller);
+        }
+        self.controllers[player] = controller;
+    }
+
+    pub fn controller_name(&self, player: usize) -> &str
+    {
+        self.controllers.get(player).map_or("heuristic", |c| c.name())
+    }
+
+    /// Ask `player`'s controller something. The controller is lifted out of
+    /// the game while it decides so it can look at the whole state.
+    pub fn ask<R>(&mut self, player: usize, decide: impl FnOnce(&mut dyn PlayerController, &GameState) -> R) -> R
+    {
+        if self.controllers.len() <= player
+        {
+            return decide(default_controller().as_mut(), self);
+        }
+
+        let mut controller = std::mem::replace(&mut self.controllers[player], Box::new(GreedyController));
+        let result = decide(controller.as_mut(), self);
+        self.controllers[player] = controller;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears, Deck};
+    use crate::game::GameStep;
+
+    #[test]
+    fn seats_use_their_own_controllers()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 5);
+        gs.set_controller(0, Box::new(GreedyController));
+        gs.set_controller(1, Box::new(ScriptedController::new([])));
+        assert_eq!(gs.controller_name(0), "greedy");
+        assert_eq!(gs.controller_name(1), "scripted");
+
+        for player in 0..2
+        {
+            gs.current_player_index = player;
+            gs.step = GameStep::Main;
+            let zones = gs.zones_mut();
+            zones.get_mut(&Zone::Hand).unwrap().clear();
+            zones.get_mut(&Zone::Hand).unwrap().push(forest());
+            zones.get_mut(&Zone::Battlefield).unwrap().push(grizzly_bears());
+            while gs.step != GameStep::EndTurn
+            {
+                gs.step();
+            }
+        }
+
+        // Greedy played its land and attacked; the empty script passed on both
+        assert_eq!(gs.players[0].zones[&Zone::Battlefield].len(), 2);
+        assert_eq!(gs.players[1].zones[&Zone::Battlefield].len(), 1);
+        assert_eq!(gs.players[1].life, 18);
+        assert!(crate::tappable::is_tapped(&gs.players[0].zones[&Zone::Battlefield][0]));
+    }
+}
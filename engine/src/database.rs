@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::ability::parse_ability;
+use crate::card::{AbilitiesFragment, Card, CardType, Rarity, CardFragmentKind, CreatureFragment, CreatureStats, Fragment, TappableFragment};
+
+/// Get the path to the card data files shipped with the project
+pub fn card_data_dir_path() -> PathBuf
+{
+    crate::music::find_web_dir().join("data/cards")
+}
+
+/// A card as written by designers in a `.toml` or `.json` data file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardDefinition
+{
+    pub name: String,
+    pub types: Vec<CardType>,
+    #[serde(default)]
+    pub cost: u32,
+    #[serde(default)]
+    pub power: Option<u8>,
+    #[serde(default)]
+    pub toughness: Option<u8>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub abilities: Vec<String>,
+    #[serde(default)]
+    pub set: Option<String>,
+    #[serde(default)]
+    pub collector_number: Option<String>,
+    #[serde(default)]
+    pub rarity: Option<Rarity>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub flavor_text: Option<String>,
+}
+
+impl CardDefinition
+{
+    fn validate(&self, path: &Path) -> Result<(), CardDatabaseError>
+    {
+        self.validate_fields()
+            .map_err(|message| CardDatabaseError::InvalidCard { path: path.to_path_buf(), card: self.name.clone(), message })?;
+
+        for (i, text) in self.abilities.iter().enumerate()
+        {
+            parse_ability(text).map_err(|e| CardDatabaseError::InvalidAbility
+            {
+                path: path.to_path_buf(),
+                card: self.name.clone(),
+                ability: i + 1,
+                text: text.clone(),
+                column: e.column,
+                message: e.message,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn validate_fields(&self) -> Result<(), String>
+    {
+        if self.name.trim().is_empty()
+        {
+            return Err(String::from("card has an empty name"));
+        }
+        if self.types.is_empty()
+        {
+            return Err(String::from("card needs at least one type"));
+        }
+
+        let is_creature = self.types.contains(&CardType::Creature);
+        match (is_creature, self.power, self.toughness)
+        {
+            (true, Some(_), Some(_)) | (false, None, None) => Ok(()),
+            (true, _, _) => Err(String::from("creatures need both power and toughness")),
+            (false, _, _) => Err(String::from("only creatures can have power and toughness")),
+        }
+    }
+
+    /// Build a fresh card instance from this definition.
+    pub fn to_card(&self) -> Card
+    {
+        let mut fragments: HashMap<CardFragmentKind, Box<dyn Fragment>> = HashMap::new();
+
+        if let (Some(power), Some(toughness)) = (self.power, self.toughness)
+        {
+            fragments.insert(
+                CardFragmentKind::Creature,
+                Box::new(CreatureFragment { stats: CreatureStats { power, toughness }, summoning_sickness: false }),
+            );
+        }
+        // Every permanent we model so far can be tapped
+        fragments.insert(CardFragmentKind::Tappable, Box::new(TappableFragment { tapped: false }));
+
+        // Ability text was checked when the definition was loaded
+        let abilities: Vec<_> = self.abilities.iter().filter_map(|text| parse_ability(text).ok()).collect();
+        if !abilities.is_empty()
+        {
+            fragments.insert(CardFragmentKind::Abilities, Box::new(AbilitiesFragment { abilities }));
+        }
+
+        Card
+        {
+            name: self.name.clone(),
+            card_types: self.types.clone(),
+            cost: self.cost,
+            keywords: self.keywords.clone(),
+            abilities: self.abilities.clone(),
+            set_code: self.set.clone(),
+            collector_number: self.collector_number.clone(),
+            rarity: self.rarity,
+            artist: self.artist.clone(),
+            flavor_text: self.flavor_text.clone(),
+            fragments,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CardDatabaseError
+{
+    Io { path: PathBuf, error: std::io::Error },
+    Parse { path: PathBuf, message: String },
+    InvalidCard { path: PathBuf, card: String, message: String },
+    InvalidAbility { path: PathBuf, card: String, ability: usize, text: String, column: usize, message: String },
+    Duplicate { path: PathBuf, card: String },
+}
+
+impl fmt::Display for CardDatabaseError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            CardDatabaseError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            CardDatabaseError::Parse { path, message } => write!(f, "{}: {}", path.display(), message),
+            CardDatabaseError::InvalidCard { path, card, message } => write!(f, "{}: card \"{}\": {}", path.display(), card, message),
+            CardDatabaseError::InvalidAbility { path, card, ability, text, column, message } =>
+                write!(f, "{}: card \"{}\", ability {} \"{}\", column {}: {}", path.display(), card, ability, text, column, message),
+            CardDatabaseError::Duplicate { path, card } => write!(f, "{}: card \"{}\" is defined more than once", path.display(), card),
+        }
+    }
+}
+
+impl std::error::Error for CardDatabaseError {}
+
+// TOML files hold a list of `[[card]]` tables; JSON files hold one card or an array.
+#[derive(Deserialize)]
+struct TomlCardFile
+{
+    #[serde(default)]
+    card: Vec<CardDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonCardFile
+{
+    Many(Vec<CardDefinition>),
+    One(CardDefinition),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CardDatabase
+{
+    cards: HashMap<String, CardDefinition>,
+}
+
+impl CardDatabase
+{
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Load every `.toml` and `.json` file under `dir`.
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> Result<Self, CardDatabaseError>
+    {
+        let mut db = CardDatabase::new();
+        let mut files: Vec<PathBuf> = walkdir::WalkDir::new(dir.as_ref())
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("toml") | Some("json")))
+            .collect();
+        files.sort();
+
+        for path in files
+        {
+            db.load_file(&path)?;
+        }
+        Ok(db)
+    }
+
+    pub fn load_file(&mut self, path: &Path) -> Result<usize, CardDatabaseError>
+    {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| CardDatabaseError::Io { path: path.to_path_buf(), error })?;
+
+        let definitions = match path.extension().and_then(|e| e.to_str())
+        {
+            Some("json") => serde_json::from_str::<JsonCardFile>(&text)
+                .map(|f| match f { JsonCardFile::Many(v) => v, JsonCardFile::One(c) => vec![c] })
+                .map_err(|e| e.to_string()),
+            _ => toml::from_str::<TomlCardFile>(&text)
+                .map(|f| f.card)
+                .map_err(|e| e.to_string()),
+        }
+        .map_err(|message| CardDatabaseError::Parse { path: path.to_path_buf(), message })?;
+
+        let count = definitions.len();
+        for definition in definitions
+        {
+            definition.validate(path)?;
+            if self.cards.contains_key(&definition.name)
+            {
+                return Err(CardDatabaseError::Duplicate { path: path.to_path_buf(), card: definition.name });
+            }
+            self.cards.insert(definition.name.clone(), definition);
+        }
+        Ok(count)
+    }
+
+    pub fn insert(&mut self, definition: CardDefinition)
+    {
+        self.cards.insert(definition.name.clone(), definition);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CardDefinition>
+    {
+        self.cards.get(name)
+    }
+
+    /// Construct a new instance of the named card.
+    pub fn create(&self, name: &str) -> Option<Card>
+    {
+        self.cards.get(name).map(|d| d.to_card())
+    }
+
+    pub fn names(&self) -> Vec<&str>
+    {
+        let mut names: Vec<&str> = self.cards.keys().map(|n| n.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// Definitions matching `predicate`, in name order.
+    pub fn filter(&self, predicate: impl Fn(&CardDefinition) -> bool) -> Vec<&CardDefinition>
+    {
+        let mut matching: Vec<&CardDefinition> = self.cards.values().filter(|d| predicate(d)).collect();
+        matching.sort_by(|a, b| a.name.cmp(&b.name));
+        matching
+    }
+
+    pub fn of_rarity(&self, rarity: Rarity) -> Vec<&CardDefinition>
+    {
+        self.filter(|d| d.rarity == Some(rarity))
+    }
+
+    pub fn in_set(&self, set_code: &str) -> Vec<&CardDefinition>
+    {
+        self.filter(|d| d.set.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(set_code)))
+    }
+
+    /// How many distinct cards of each rarity the database holds, optionally within one set.
+    pub fn rarity_distribution(&self, set_code: Option<&str>) -> BTreeMap<Rarity, usize>
+    {
+        let mut counts = BTreeMap::new();
+        let cards = match set_code
+        {
+            Some(code) => self.in_set(code),
+            None => self.filter(|_| true),
+        };
+        for rarity in cards.iter().filter_map(|d| d.rarity)
+        {
+            *counts.entry(rarity).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.cards.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn write_temp(name: &str, text: &str) -> PathBuf
+    {
+        let dir = std::env::temp_dir().join(format!("tcg-sim-db-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_toml_and_builds_fresh_cards()
+    {
+        let path = write_temp("cards.toml", r#"
+            [[card]]
+            name = "Forest"
+            types = ["Land"]
+
+            [[card]]
+            name = "Grizzly Bears"
+            types = ["Creature"]
+            cost = 2
+            power = 2
+            toughness = 2
+            set = "M10"
+            rarity = "common"
+            artist = "D. J. Cleland-Hura"
+        "#);
+
+        let mut db = CardDatabase::new();
+        assert_eq!(db.load_file(&path).unwrap(), 2);
+        assert_eq!(db.rarity_distribution(Some("m10")).get(&Rarity::Common), Some(&1));
+
+        let mut bears = db.create("Grizzly Bears").unwrap();
+        crate::tappable::set_tapped(&mut bears, true);
+        let fresh = db.create("Grizzly Bears").unwrap();
+        assert!(!crate::tappable::is_tapped(&fresh));
+        assert_eq!(crate::creature::creature_stats(&fresh).unwrap().toughness, 2);
+        assert_eq!(fresh.rarity, Some(Rarity::Common));
+        assert!(db.create("Lightning Bolt").is_none());
+    }
+
+    #[test]
+    fn ability_errors_point_at_card_and_column()
+    {
+        let path = write_temp("abilities.toml", r#"
+            [[card]]
+            name = "Prodigal Bear"
+            types = ["Creature"]
+            cost = 3
+            power = 2
+            toughness = 2
+            abilities = ["when ETB: draw 1", "{T}: add {Q}"]
+        "#);
+
+        match CardDatabase::new().load_file(&path).unwrap_err()
+        {
+            CardDatabaseError::InvalidAbility { card, ability, column, .. } =>
+            {
+                assert_eq!((card.as_str(), ability, column), ("Prodigal Bear", 2, 10));
+            }
+            other => panic!("unexpected error {}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_entries_name_the_file_and_card()
+    {
+        let path = write_temp("bad.json", r#"[{ "name": "Bad Bear", "types": ["Creature"], "cost": 2, "power": 2 }]"#);
+        let err = CardDatabase::new().load_file(&path).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("bad.json") && message.contains("Bad Bear"), "{}", message);
+    }
+}
@@ -0,0 +1,345 @@
+use std::fmt;
+
+use crate::card::{Card, Deck};
+use crate::database::CardDatabase;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DeckFormat
+{
+    Text,   // "4 Grizzly Bears", sideboard after a blank line or "Sideboard"
+    Arena,  // "Deck" / "Sideboard" headers, optional "(SET) 123" suffixes
+    Mtgo,   // MTGO .dek XML
+}
+
+impl DeckFormat
+{
+    /// Guess the format from the file contents.
+    pub fn detect(text: &str) -> DeckFormat
+    {
+        let trimmed = text.trim_start();
+        if trimmed.starts_with("<?xml") || trimmed.starts_with("<Deck")
+        {
+            DeckFormat::Mtgo
+        }
+        else if trimmed.lines().next().map(|l| l.trim() == "Deck").unwrap_or(false)
+        {
+            DeckFormat::Arena
+        }
+        else
+        {
+            DeckFormat::Text
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeckListError
+{
+    pub line: usize,   // 1-based
+    pub message: String,
+}
+
+impl fmt::Display for DeckListError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for DeckListError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeckEntry
+{
+    pub count: u32,
+    pub name: String,
+    pub line: usize,
+}
+
+/// A deck list by card name, before names are turned into cards.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeckList
+{
+    pub main: Vec<DeckEntry>,
+    pub sideboard: Vec<DeckEntry>,
+}
+
+impl DeckList
+{
+    pub fn parse(text: &str, format: DeckFormat) -> Result<DeckList, Vec<DeckListError>>
+    {
+        match format
+        {
+            DeckFormat::Text | DeckFormat::Arena => parse_text(text),
+            DeckFormat::Mtgo => parse_mtgo(text),
+        }
+    }
+
+    /// Group a deck's cards by name, keeping first-seen order.
+    pub fn from_deck(deck: &Deck) -> DeckList
+    {
+        DeckList { main: group(&deck.cards), sideboard: group(&deck.sideboard) }
+    }
+
+    /// Resolve every name against `db`. Unknown names are reported with their line.
+    pub fn to_deck(&self, db: &CardDatabase) -> Result<Deck, Vec<DeckListError>>
+    {
+        let mut errors = Vec::new();
+        let mut resolve = |entries: &[DeckEntry]| -> Vec<Card>
+        {
+            let mut cards = Vec::new();
+            for entry in entries
+            {
+                match db.get(&entry.name)
+                {
+                    Some(definition) => cards.extend((0..entry.count).map(|_| definition.to_card())),
+                    None => errors.push(DeckListError { line: entry.line, message: format!("unknown card \"{}\"", entry.name) }),
+                }
+            }
+            cards
+        };
+
+        let cards = resolve(&self.main);
+        let sideboard = resolve(&self.sideboard);
+        if errors.is_empty() { Ok(Deck { cards, sideboard }) } else { Err(errors) }
+    }
+
+    pub fn write(&self, format: DeckFormat) -> String
+    {
+        let mut out = String::new();
+        match format
+        {
+            DeckFormat::Text | DeckFormat::Arena =>
+            {
+                if format == DeckFormat::Arena
+                {
+                    out.push_str("Deck\n");
+                }
+                for entry in &self.main
+                {
+                    out.push_str(&format!("{} {}\n", entry.count, entry.name));
+                }
+                if !self.sideboard.is_empty()
+                {
+                    out.push_str("\nSideboard\n");
+                    for entry in &self.sideboard
+                    {
+                        out.push_str(&format!("{} {}\n", entry.count, entry.name));
+                    }
+                }
+            }
+            DeckFormat::Mtgo =>
+            {
+                out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+                out.push_str("<Deck xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\n");
+                out.push_str("  <NetDeckID>0</NetDeckID>\n");
+                out.push_str("  <PreconstructedDeckID>0</PreconstructedDeckID>\n");
+                for (entries, sideboard) in [(&self.main, false), (&self.sideboard, true)]
+                {
+                    for entry in entries
+                    {
+                        out.push_str(&format!(
+                            "  <Cards CatID=\"0\" Quantity=\"{}\" Sideboard=\"{}\" Name=\"{}\" Annotation=\"0\" />\n",
+                            entry.count, sideboard, xml_escape(&entry.name)));
+                    }
+                }
+                out.push_str("</Deck>\n");
+            }
+        }
+        out
+    }
+}
+
+/// Parse a deck list and resolve it against `db` in one go.
+pub fn parse_deck(text: &str, format: DeckFormat, db: &CardDatabase) -> Result<Deck, Vec<DeckListError>>
+{
+    DeckList::parse(text, format)?.to_deck(db)
+}
+
+pub fn write_deck(deck: &Deck, format: DeckFormat) -> String
+{
+    DeckList::from_deck(deck).write(format)
+}
+
+fn group(cards: &[Card]) -> Vec<DeckEntry>
+{
+    let mut entries: Vec<DeckEntry> = Vec::new();
+    for card in cards
+    {
+        match entries.iter_mut().find(|e| e.name == card.name)
+        {
+            Some(entry) => entry.count += 1,
+            None => entries.push(DeckEntry { count: 1, name: card.name.clone(), line: 0 }),
+        }
+    }
+    entries
+}
+
+// Handles both plain text and Arena exports.
+fn parse_text(text: &str) -> Result<DeckList, Vec<DeckListError>>
+{
+    let mut list = DeckList::default();
+    let mut errors = Vec::new();
+    let mut in_sideboard = false;
+    let mut seen_cards = false;
+
+    for (i, raw) in text.lines().enumerate()
+    {
+        let line_no = i + 1;
+        let mut line = raw.trim();
+
+        if line.is_empty()
+        {
+            // A blank line after the main deck starts the sideboard
+            if seen_cards
+            {
+                in_sideboard = true;
+            }
+            continue;
+        }
+        if line.starts_with("//") || line.starts_with('#')
+        {
+            continue;
+        }
+
+        match line.to_ascii_lowercase().trim_end_matches(':')
+        {
+            "deck" | "main" | "maindeck" | "commander" | "companion" => { in_sideboard = false; continue; }
+            "sideboard" => { in_sideboard = true; continue; }
+            _ => {}
+        }
+
+        let mut sideboard_line = in_sideboard;
+        if let Some(rest) = line.strip_prefix("SB:")
+        {
+            sideboard_line = true;
+            line = rest.trim();
+        }
+
+        match parse_entry(line)
+        {
+            Some((count, name)) =>
+            {
+                let entry = DeckEntry { count, name, line: line_no };
+                if sideboard_line { list.sideboard.push(entry); } else { list.main.push(entry); }
+                seen_cards = true;
+            }
+            None => errors.push(DeckListError { line: line_no, message: format!("expected \"<count> <card name>\", got \"{}\"", line) }),
+        }
+    }
+
+    if errors.is_empty() { Ok(list) } else { Err(errors) }
+}
+
+// "4 Grizzly Bears", "4x Grizzly Bears" or Arena's "4 Grizzly Bears (M10) 189".
+fn parse_entry(line: &str) -> Option<(u32, String)>
+{
+    let (count, rest) = line.split_once(char::is_whitespace)?;
+    let count: u32 = count.trim_end_matches(['x', 'X']).parse().ok()?;
+    let mut name = rest.trim();
+
+    if let Some(open) = name.rfind(" (")
+        && name[open..].contains(')')
+    {
+        name = name[..open].trim_end();
+    }
+
+    if name.is_empty() || count == 0 { None } else { Some((count, name.to_string())) }
+}
+
+fn parse_mtgo(text: &str) -> Result<DeckList, Vec<DeckListError>>
+{
+    let mut list = DeckList::default();
+    let mut errors = Vec::new();
+
+    for (i, line) in text.lines().enumerate()
+    {
+        let line_no = i + 1;
+        let line = line.trim();
+        if !line.starts_with("<Cards ")
+        {
+            continue;
+        }
+
+        let count = xml_attribute(line, "Quantity").and_then(|q| q.parse::<u32>().ok());
+        let name = xml_attribute(line, "Name");
+        let sideboard = xml_attribute(line, "Sideboard").map(|s| s.eq_ignore_ascii_case("true")).unwrap_or(false);
+
+        match (count, name)
+        {
+            (Some(count), Some(name)) if count > 0 && !name.is_empty() =>
+            {
+                let entry = DeckEntry { count, name, line: line_no };
+                if sideboard { list.sideboard.push(entry); } else { list.main.push(entry); }
+            }
+            _ => errors.push(DeckListError { line: line_no, message: String::from("<Cards> element needs a Quantity and a Name") }),
+        }
+    }
+
+    if errors.is_empty() { Ok(list) } else { Err(errors) }
+}
+
+fn xml_attribute(element: &str, attribute: &str) -> Option<String>
+{
+    let key = format!(" {}=\"", attribute);
+    let start = element.find(&key)? + key.len();
+    let end = element[start..].find('"')? + start;
+    Some(xml_unescape(&element[start..end]))
+}
+
+fn xml_escape(s: &str) -> String
+{
+    s.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;").replace('\'', "&apos;")
+}
+
+fn xml_unescape(s: &str) -> String
+{
+    s.replace("&quot;", "\"").replace("&lt;", "<").replace("&gt;", ">").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::database::CardDefinition;
+    use crate::card::CardType;
+
+    fn db() -> CardDatabase
+    {
+        let mut db = CardDatabase::new();
+        db.insert(CardDefinition { name: String::from("Forest"), types: vec![CardType::Land], cost: 0, power: None, toughness: None, ..Default::default() });
+        db.insert(CardDefinition { name: String::from("Grizzly Bears"), types: vec![CardType::Creature], cost: 2, power: Some(2), toughness: Some(2), ..Default::default() });
+        db
+    }
+
+    #[test]
+    fn arena_export_with_sideboard_round_trips()
+    {
+        let text = "Deck\n20 Forest (M21) 274\n4 Grizzly Bears (10E) 268\n\nSideboard\n2 Grizzly Bears (10E) 268\n";
+        assert_eq!(DeckFormat::detect(text), DeckFormat::Arena);
+
+        let deck = parse_deck(text, DeckFormat::Arena, &db()).unwrap();
+        assert_eq!(deck.cards.len(), 24);
+        assert_eq!(deck.sideboard.len(), 2);
+
+        for format in [DeckFormat::Text, DeckFormat::Arena, DeckFormat::Mtgo]
+        {
+            let written = write_deck(&deck, format);
+            let reparsed = parse_deck(&written, DeckFormat::detect(&written), &db()).unwrap();
+            assert_eq!(DeckList::from_deck(&reparsed), DeckList::from_deck(&deck), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn unknown_names_report_line_numbers()
+    {
+        let text = "4 Grizzly Bears\n3 Lightning Bolt\nnonsense\n";
+        let errors = DeckList::parse(text, DeckFormat::Text).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 3);
+
+        let errors = parse_deck("4 Grizzly Bears\n3 Lightning Bolt\n", DeckFormat::Text, &db()).unwrap_err();
+        assert_eq!(errors, vec![DeckListError { line: 2, message: String::from("unknown card \"Lightning Bolt\"") }]);
+    }
+}
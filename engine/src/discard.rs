@@ -0,0 +1,248 @@
+use rand::Rng;
+
+use crate::card::{Card, CardFragmentKind, CardType, ExiledFragment, MadnessFragment};
+use crate::event::GameEvent;
+use crate::game::{GameState, Zone};
+
+pub fn madness_cost(card: &Card) -> Option<u32>
+{
+    card.fragments.get(&CardFragmentKind::Madness)
+        .and_then(|f| f.as_any().downcast_ref::<MadnessFragment>().map(|mf| mf.cost))
+}
+
+/// Whether `card` is in exile because it was discarded with madness.
+pub fn is_madness_exiled(card: &Card) -> bool
+{
+    card.fragments.get(&CardFragmentKind::Exiled)
+        .and_then(|f| f.as_any().downcast_ref::<ExiledFragment>())
+        .is_some_and(|ef| ef.madness)
+}
+
+/// Mana needed to cast `card` from `zone`; cards exiled by a madness discard
+/// use their madness cost.
+pub fn cost_in_zone(card: &Card, zone: Zone) -> u32
+{
+    match (zone, madness_cost(card))
+    {
+        (Zone::Exile, Some(cost)) if is_madness_exiled(card) => cost,
+        _ => card.cost,
+    }
+}
+
+impl GameState
+{
+    /// Discard the card at `hand_index`, running any "when discarded" effects.
+    pub fn discard(&mut self, player: usize, hand_index: usize) -> bool
+    {
+        let hand = self.players[player].zones.get_mut(&Zone::Hand).unwrap();
+        if hand_index >= hand.len()
+        {
+            return false;
+        }
+        let mut card = hand.remove(hand_index);
+
+        vlog!(crate::ELoggingVerbosity::Verbose, "Discard {}", card.name);
+        self.emit(GameEvent::Discarded { player, card: card.name.clone() });
+
+        if madness_cost(&card).is_some()
+        {
+            // Madness: exile it instead, castable for its madness cost this turn
+            let fragment = ExiledFragment { face_down: false, playable_by: Some(player), playable_until_turn: Some(self.turns), linked_to: None, madness: true };
+            card.fragments.insert(CardFragmentKind::Exiled, Box::new(fragment));
+            self.emit(GameEvent::Exiled { player, card: Some(card.name.clone()), face_down: false });
+            self.players[player].zones.get_mut(&Zone::Exile).unwrap().push(card);
+        }
+        else
+        {
+            self.players[player].zones.get_mut(&Zone::Graveyard).unwrap().push(card);
+        }
+        true
+    }
+
+    /// Discard `count` cards at random using the game's RNG. Returns how many were discarded.
+    pub fn discard_random(&mut self, player: usize, count: usize) -> usize
+    {
+        let mut discarded = 0;
+        while discarded < count
+        {
+            let hand_size = self.players[player].zones.get(&Zone::Hand).unwrap().len();
+            if hand_size == 0
+            {
+                break;
+            }
+            let index = self.rng.gen_range(0..hand_size);
+            self.discard(player, index);
+            discarded += 1;
+        }
+        discarded
+    }
+
+    /// Discard `count` cards, with `choose` picking a hand index each time.
+    pub fn discard_chosen<F>(&mut self, player: usize, count: usize, mut choose: F) -> usize
+    where
+        F: FnMut(&[Card]) -> usize,
+    {
+        let mut discarded = 0;
+        while discarded < count
+        {
+            let index =
+            {
+                let hand = self.players[player].zones.get(&Zone::Hand).unwrap();
+                if hand.is_empty()
+                {
+                    break;
+                }
+                choose(hand).min(hand.len() - 1)
+            };
+            self.discard(player, index);
+            discarded += 1;
+        }
+        discarded
+    }
+
+    /// Put madness cards whose casting window has closed into their owners'
+    /// graveyards. Runs at the end of every turn, after the cleanup discard.
+    pub fn expire_madness(&mut self)
+    {
+        let turn = self.turns;
+        for player in &mut self.players
+        {
+            let exile = std::mem::take(player.zones.get_mut(&Zone::Exile).unwrap());
+            let (expired, staying): (Vec<Card>, Vec<Card>) = exile.into_iter().partition(|c|
+            {
+                is_madness_exiled(c) && crate::exile::playable_until(c).is_some_and(|t| t <= turn)
+            });
+            player.zones.insert(Zone::Exile, staying);
+
+            for mut card in expired
+            {
+                crate::exile::clear_exiled(&mut card);
+                player.zones.get_mut(&Zone::Graveyard).unwrap().push(card);
+            }
+        }
+    }
+
+    /// Cleanup-step discard down to the player's maximum hand size.
+    pub fn discard_to_hand_size(&mut self, player: usize) -> usize
+    {
+        let Some(max) = self.players[player].max_hand_size else { return 0; };
+        let excess = self.players[player].zones.get(&Zone::Hand).unwrap().len().saturating_sub(max);
+
+        // The player's controller picks each card
+        for _ in 0..excess
+        {
+            let index = self.ask(player, |c, g| c.choose_discard(g, player));
+            let hand_size = self.players[player].zones.get(&Zone::Hand).unwrap().len();
+            self.discard(player, index.min(hand_size.saturating_sub(1)));
+        }
+        excess
+    }
+}
+
+/// Built-in discard choice: pitch a land once there is enough mana for
+/// everything in hand, otherwise the most expensive spell.
+pub fn autopilot_discard(hand: &[Card], lands_in_play: u32) -> usize
+{
+    let most_expensive = hand.iter().enumerate()
+        .filter(|(_, c)| !c.is_type(CardType::Land))
+        .max_by_key(|(_, c)| c.cost);
+    let land = hand.iter().position(|c| c.is_type(CardType::Land));
+
+    match (most_expensive, land)
+    {
+        (Some((_, spell)), Some(land)) if spell.cost <= lands_in_play => land,
+        (Some((i, _)), _) => i,
+        (None, Some(land)) => land,
+        (None, None) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears, Deck};
+    use crate::game::GameStep;
+
+    #[test]
+    fn cleanup_discards_to_max_hand_size()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 3);
+        {
+            let hand = gs.zones_mut().get_mut(&Zone::Hand).unwrap();
+            hand.clear();
+            for _ in 0..5 { hand.push(forest()); }
+            for _ in 0..4 { hand.push(grizzly_bears()); }
+        }
+        gs.zones_mut().get_mut(&Zone::Graveyard).unwrap().clear();
+        gs.step = GameStep::EndTurn;
+        gs.step();
+
+        let player = &gs.players[0];
+        assert_eq!(player.zones[&Zone::Hand].len(), 7);
+        assert_eq!(player.zones[&Zone::Graveyard].len(), 2);
+    }
+
+    #[test]
+    fn madness_card_is_exiled_and_castable_cheaply()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 3);
+        gs.step = GameStep::Main;
+        let mut bears = grizzly_bears();
+        bears.fragments.insert(CardFragmentKind::Madness, Box::new(MadnessFragment { cost: 1 }));
+        {
+            let zones = gs.zones_mut();
+            zones.get_mut(&Zone::Hand).unwrap().clear();
+            zones.get_mut(&Zone::Hand).unwrap().push(bears);
+            zones.get_mut(&Zone::Battlefield).unwrap().push(forest());
+        }
+
+        assert!(gs.discard(0, 0));
+        assert_eq!(gs.zones()[&Zone::Exile].len(), 1);
+        assert!(gs.cast_from_exile(0));
+        assert_eq!(gs.zones()[&Zone::Battlefield].len(), 2);
+    }
+
+    fn madness_bears() -> Card
+    {
+        let mut bears = grizzly_bears();
+        bears.fragments.insert(CardFragmentKind::Madness, Box::new(MadnessFragment { cost: 1 }));
+        bears
+    }
+
+    #[test]
+    fn uncast_madness_card_goes_to_graveyard_at_end_of_turn()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 3);
+        {
+            let zones = gs.zones_mut();
+            zones.get_mut(&Zone::Hand).unwrap().clear();
+            zones.get_mut(&Zone::Hand).unwrap().push(madness_bears());
+            zones.get_mut(&Zone::Graveyard).unwrap().clear();
+        }
+        assert!(gs.discard(0, 0));
+
+        // Cleanup discards another madness card; it can't be cast either
+        gs.players[0].max_hand_size = Some(0);
+        gs.zones_mut().get_mut(&Zone::Hand).unwrap().push(madness_bears());
+        gs.step = GameStep::EndTurn;
+        gs.step();
+
+        let zones = &gs.players[0].zones;
+        assert!(zones[&Zone::Exile].is_empty());
+        assert_eq!(zones[&Zone::Graveyard].len(), 2);
+        assert!(!zones[&Zone::Graveyard][0].fragments.contains_key(&CardFragmentKind::Exiled));
+    }
+
+    #[test]
+    fn madness_cost_needs_a_madness_discard()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 3);
+        gs.zones_mut().get_mut(&Zone::Hand).unwrap().push(madness_bears());
+        let last = gs.zones()[&Zone::Hand].len() - 1;
+        assert!(gs.exile_card(0, Zone::Hand, last, false));
+
+        let exiled = gs.zones()[&Zone::Exile].last().unwrap();
+        assert_eq!(cost_in_zone(exiled, Zone::Exile), 2);
+    }
+}
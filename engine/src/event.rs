@@ -0,0 +1,31 @@
+use serde::{Serialize, Deserialize};
+
+use crate::library::LibraryPosition;
+
+/// Something observable that happened during the game. Events are appended to
+/// `GameState::events` in the order they occur.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameEvent
+{
+    LibraryShuffled { player: usize },
+    /// `card` is only named when it went somewhere public.
+    LibrarySearched { player: usize, found: bool, card: Option<String> },
+    Scried { player: usize, kept_on_top: u32, put_on_bottom: u32 },
+    Surveilled { player: usize, kept_on_top: u32, put_in_graveyard: u32 },
+    Milled { player: usize, card: String },
+    PutInLibrary { player: usize, card: String, position: LibraryPosition },
+    Revealed { player: usize, card: String, to: Vec<usize> },
+    /// `card` is None when exiled face down.
+    Exiled { player: usize, card: Option<String>, face_down: bool },
+    ReturnedFromExile { player: usize, card: String },
+    Discarded { player: usize, card: String },
+    Drew { player: usize, count: u32 },
+    Mulliganed { player: usize, mulligans: u32 },
+    /// `player` ended their main phase holding `hand_size` cards with
+    /// `untapped_mana` unspent and, if `land_drop`, a land drop unused.
+    PassedMain { player: usize, hand_size: usize, untapped_mana: u32, land_drop: bool },
+    /// Defending `player` let `unblocked` attackers through while holding
+    /// `hand_size` cards, with `untapped_mana` up and `idle_blockers` able
+    /// creatures not blocking.
+    DeclinedBlocks { player: usize, hand_size: usize, untapped_mana: u32, unblocked: u32, idle_blockers: u32 },
+}
@@ -0,0 +1,283 @@
+use crate::card::{Card, CardFragmentKind, ExiledFragment, ExileLinkFragment};
+use crate::event::GameEvent;
+use crate::game::{GameState, Zone};
+
+fn exiled(card: &Card) -> Option<&ExiledFragment>
+{
+    card.fragments.get(&CardFragmentKind::Exiled)
+        .and_then(|f| f.as_any().downcast_ref::<ExiledFragment>())
+}
+
+pub fn is_face_down(card: &Card) -> bool
+{
+    exiled(card).map(|ef| ef.face_down).unwrap_or(false)
+}
+
+pub fn is_playable_from_exile(card: &Card, player: usize, turn: u32) -> bool
+{
+    exiled(card)
+        .map(|ef| ef.playable_by == Some(player) && ef.playable_until_turn.is_none_or(|t| turn <= t))
+        .unwrap_or(false)
+}
+
+pub fn playable_exile_indices(exile: &[Card], player: usize, turn: u32) -> Vec<usize>
+{
+    exile.iter().enumerate()
+        .filter(|(_, c)| is_playable_from_exile(c, player, turn))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Last turn the card may be played from exile, if its window is limited.
+pub fn playable_until(card: &Card) -> Option<u32>
+{
+    exiled(card).and_then(|ef| ef.playable_until_turn)
+}
+
+pub fn linked_exile_id(card: &Card) -> Option<u32>
+{
+    exiled(card).and_then(|ef| ef.linked_to)
+}
+
+pub fn exile_link_id(card: &Card) -> Option<u32>
+{
+    card.fragments.get(&CardFragmentKind::ExileLink)
+        .and_then(|f| f.as_any().downcast_ref::<ExileLinkFragment>().map(|lf| lf.link_id))
+}
+
+/// Remove exile-only state when a card leaves exile.
+pub fn clear_exiled(card: &mut Card)
+{
+    card.fragments.remove(&CardFragmentKind::Exiled);
+}
+
+impl GameState
+{
+    /// Move a card from one of `player`'s zones into their exile.
+    pub fn exile_card(&mut self, player: usize, from: Zone, index: usize, face_down: bool) -> bool
+    {
+        let exiled = ExiledFragment { face_down, playable_by: None, playable_until_turn: None, linked_to: None, madness: false };
+        self.exile_with(player, from, index, exiled)
+    }
+
+    /// Let `owner` play their exiled card at `exile_index` (adventures, impulse
+    /// draw). Only owners can be granted this, since playing from exile looks
+    /// in the acting player's own exile. `until_turn` of None keeps it
+    /// playable for as long as it stays exiled.
+    pub fn grant_play_from_exile(&mut self, owner: usize, exile_index: usize, until_turn: Option<u32>)
+    {
+        let exile = self.players[owner].zones.get_mut(&Zone::Exile).unwrap();
+        if let Some(ef) = exile.get_mut(exile_index)
+            .and_then(|c| c.fragments.get_mut(&CardFragmentKind::Exiled))
+            .and_then(|f| f.as_any_mut().downcast_mut::<ExiledFragment>())
+        {
+            ef.playable_by = Some(owner);
+            ef.playable_until_turn = until_turn;
+        }
+    }
+
+    /// Impulse draw: exile the top `count` cards of the library face up;
+    /// `player` may play them until the end of this turn.
+    pub fn exile_top_playable(&mut self, player: usize, count: usize) -> usize
+    {
+        let mut exiled = 0;
+        while exiled < count
+        {
+            let top = match self.players[player].zones.get(&Zone::Library).unwrap().len()
+            {
+                0 => break,
+                n => n - 1,
+            };
+            let fragment = ExiledFragment { face_down: false, playable_by: Some(player), playable_until_turn: Some(self.turns), linked_to: None, madness: false };
+            self.exile_with(player, Zone::Library, top, fragment);
+            exiled += 1;
+        }
+        exiled
+    }
+
+    /// Exile `target_player`'s permanent at `target_index` until the permanent
+    /// at `source_index` leaves the battlefield (Oblivion Ring style).
+    pub fn exile_until_leaves(&mut self, source_player: usize, source_index: usize, target_player: usize, target_index: usize) -> bool
+    {
+        let Some(source) = self.players[source_player].zones.get(&Zone::Battlefield).unwrap().get(source_index) else { return false; };
+        if source_player == target_player && source_index == target_index
+        {
+            return false;
+        }
+
+        let link_id = match exile_link_id(source)
+        {
+            Some(id) => id,
+            None =>
+            {
+                let id = self.next_link_id;
+                self.next_link_id += 1;
+                let source = &mut self.players[source_player].zones.get_mut(&Zone::Battlefield).unwrap()[source_index];
+                source.fragments.insert(CardFragmentKind::ExileLink, Box::new(ExileLinkFragment { link_id: id }));
+                id
+            }
+        };
+
+        let fragment = ExiledFragment { face_down: false, playable_by: None, playable_until_turn: None, linked_to: Some(link_id), madness: false };
+        self.exile_with(target_player, Zone::Battlefield, target_index, fragment)
+    }
+
+    /// Move a permanent off the battlefield. If it was holding cards in exile,
+    /// those return to the battlefield under their owners' control.
+    pub fn move_from_battlefield(&mut self, player: usize, index: usize, to: Zone) -> bool
+    {
+        if to == Zone::Exile
+        {
+            return self.exile_card(player, Zone::Battlefield, index, false);
+        }
+
+        let Some(card) = self.remove_from_battlefield(player, index) else { return false; };
+        let dies = if to == Zone::Graveyard && crate::creature::is_creature(&card)
+        {
+            crate::ability::triggered_effects(&card, crate::ability::Trigger::Dies)
+        }
+        else
+        {
+            Vec::new()
+        };
+        self.players[player].zones.get_mut(&to).unwrap().push(card);
+
+        self.resolve_effects(player, &dies);
+        true
+    }
+
+    fn remove_from_battlefield(&mut self, player: usize, index: usize) -> Option<Card>
+    {
+        let battlefield = self.players[player].zones.get_mut(&Zone::Battlefield).unwrap();
+        if index >= battlefield.len()
+        {
+            return None;
+        }
+
+        let mut card = battlefield.remove(index);
+        if let Some(link_id) = exile_link_id(&card)
+        {
+            card.fragments.remove(&CardFragmentKind::ExileLink);
+            self.return_linked_exile(link_id);
+        }
+        Some(card)
+    }
+
+    fn return_linked_exile(&mut self, link_id: u32)
+    {
+        for player in 0..self.players.len()
+        {
+            let returning: Vec<Card> =
+            {
+                let exile = self.players[player].zones.get_mut(&Zone::Exile).unwrap();
+                let (returning, staying) = std::mem::take(exile).into_iter()
+                    .partition(|c| linked_exile_id(c) == Some(link_id));
+                *exile = staying;
+                returning
+            };
+
+            for mut card in returning
+            {
+                clear_exiled(&mut card);
+                crate::creature::set_summoning_sickness(&mut card, true);
+                self.emit(GameEvent::ReturnedFromExile { player, card: card.name.clone() });
+                self.players[player].zones.get_mut(&Zone::Battlefield).unwrap().push(card);
+            }
+        }
+    }
+
+    fn exile_with(&mut self, player: usize, from: Zone, index: usize, exiled: ExiledFragment) -> bool
+    {
+        let card = if from == Zone::Battlefield
+        {
+            self.remove_from_battlefield(player, index)
+        }
+        else
+        {
+            let zone = self.players[player].zones.get_mut(&from).unwrap();
+            (index < zone.len()).then(|| zone.remove(index))
+        };
+        let Some(mut card) = card else { return false; };
+
+        let face_down = exiled.face_down;
+        card.fragments.insert(CardFragmentKind::Exiled, Box::new(exiled));
+
+        // A face-down card's identity stays hidden from everyone reading the log
+        let name = (!face_down).then(|| card.name.clone());
+        self.emit(GameEvent::Exiled { player, card: name, face_down });
+        self.players[player].zones.get_mut(&Zone::Exile).unwrap().push(card);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears, Deck};
+
+    #[test]
+    fn exiled_card_returns_when_source_leaves()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 1);
+        gs.players[0].zones.get_mut(&Zone::Battlefield).unwrap().push(forest());
+        gs.players[1].zones.get_mut(&Zone::Battlefield).unwrap().push(grizzly_bears());
+
+        assert!(gs.exile_until_leaves(0, 0, 1, 0));
+        assert!(gs.players[1].zones[&Zone::Battlefield].is_empty());
+        assert_eq!(gs.players[1].zones[&Zone::Exile].len(), 1);
+
+        assert!(gs.move_from_battlefield(0, 0, Zone::Graveyard));
+        assert!(gs.players[1].zones[&Zone::Exile].is_empty());
+        let returned = &gs.players[1].zones[&Zone::Battlefield][0];
+        assert_eq!(returned.name, "Grizzly Bears");
+        assert!(!returned.fragments.contains_key(&CardFragmentKind::Exiled));
+    }
+
+    #[test]
+    fn face_down_exile_hides_the_name()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 1);
+        assert!(gs.exile_card(0, Zone::Hand, 0, true));
+        assert_eq!(gs.events.last(), Some(&GameEvent::Exiled { player: 0, card: None, face_down: true }));
+        assert!(is_face_down(&gs.players[0].zones[&Zone::Exile][0]));
+    }
+
+    #[test]
+    fn only_the_owner_can_play_a_granted_card()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 1);
+        gs.step = crate::game::GameStep::Main;
+        gs.players[1].zones.get_mut(&Zone::Hand).unwrap().push(forest());
+        let index = gs.players[1].zones[&Zone::Hand].len() - 1;
+        assert!(gs.exile_card(1, Zone::Hand, index, false));
+        gs.grant_play_from_exile(1, 0, None);
+
+        let card = &gs.players[1].zones[&Zone::Exile][0];
+        assert!(is_playable_from_exile(card, 1, gs.turns));
+        assert!(!is_playable_from_exile(card, 0, gs.turns));
+        assert!(playable_exile_indices(&gs.players[0].zones[&Zone::Exile], 0, gs.turns).is_empty());
+
+        gs.current_player_index = 1;
+        assert!(gs.play_land_from_exile(0));
+        assert_eq!(gs.players[1].zones[&Zone::Battlefield].last().unwrap().name, "Forest");
+    }
+
+    #[test]
+    fn impulse_draw_is_playable_this_turn_only()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 1);
+        gs.step = crate::game::GameStep::Main;
+        gs.turns = 3;
+        gs.players[0].zones.get_mut(&Zone::Library).unwrap().push(forest());
+
+        assert_eq!(gs.exile_top_playable(0, 1), 1);
+        let exile = &gs.players[0].zones[&Zone::Exile];
+        assert!(is_playable_from_exile(&exile[0], 0, 3));
+        assert!(!is_playable_from_exile(&exile[0], 1, 3));
+        assert!(!is_playable_from_exile(&exile[0], 0, 4));
+
+        assert!(gs.play_land_from_exile(0));
+        assert!(gs.players[0].zones[&Zone::Exile].is_empty());
+    }
+}
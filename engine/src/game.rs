@@ -1,12 +1,20 @@
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 
+use crate::ability::{Effect, Trigger};
+use crate::action::Action;
 use crate::card::{Card, Deck};
+use crate::controller::{default_controller, PlayerController};
+use crate::event::GameEvent;
+use crate::mulligan::MulliganStrategy;
+use crate::turn::TurnQueue;
+use crate::validator::DeckValidator;
 use crate::ELoggingVerbosity;
 
-#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameStep 
 {
     StartTurn,
@@ -39,6 +47,12 @@ pub enum StepCommand
     RunGame,         // "g"
     RunDeck,         // "d"
     RunAll,          // "r"
+    Play,            // "p"
+    Mulligans,       // "m"
+    Draft,           // "b"
+    Matches,         // "w"
+    VsMcts,          // "v"
+    Tournament,      // "l"
     Quit,            // "q"
     Invalid,         // anything else
 }
@@ -46,6 +60,7 @@ pub enum StepCommand
 pub struct ProgramState 
 {
     pub step_mode: StepCommand,
+    pub validator: DeckValidator,   // decks must pass before the sim optimizes them
 }
 
 impl ProgramState
@@ -55,6 +70,7 @@ impl ProgramState
         ProgramState
         {
             step_mode: StepCommand::StepPhase,
+            validator: DeckValidator::freeform(60),
         }
     }
 }
@@ -64,25 +80,63 @@ pub struct Player
 {
     pub life: i32,
     pub zones: HashMap<Zone, Vec<Card>>,
+    #[serde(default)]
+    pub lands_played_this_turn: u32,
+    #[serde(default = "default_land_drops_per_turn")]
+    pub land_drops_per_turn: u32,    // base allowance, raised by static effects
+    #[serde(default)]
+    pub extra_land_drops: u32,       // granted by one-shot effects, cleared each turn
+    #[serde(default = "default_max_hand_size")]
+    pub max_hand_size: Option<usize>, // None means no maximum
+}
+
+pub const OPENING_HAND_SIZE: usize = 7;
+
+/// Draw an opening hand off the top of `library`, both for the first deal
+/// and for every mulligan (see mulligan.rs for keep strategies).
+pub fn draw_opening_hand(library: &mut Vec<Card>) -> Vec<Card>
+{
+    let mut hand = library.split_off(library.len().saturating_sub(OPENING_HAND_SIZE));
+    hand.reverse();
+    hand
+}
+
+fn default_max_hand_size() -> Option<usize>
+{
+    Some(7)
+}
+
+fn default_land_drops_per_turn() -> u32
+{
+    1
 }
 
 impl Player
 {
-    pub fn new(deck: &Deck) -> Self
+    /// Shuffle and deal the opening seven. Mulligans come later, in
+    /// [`GameState::resolve_mulligans`], which asks each seat's controller via
+    /// `keep_hand`, unless the seat was given a strategy with
+    /// [`GameState::set_mulligan_strategy`].
+    pub fn new(deck: &Deck, rng: &mut StdRng) -> Self
     {
-        let mut rng = thread_rng();
         let mut library = deck.cards.clone();
-        library.shuffle(&mut rng);
+        library.shuffle(rng);
+        let hand = draw_opening_hand(&mut library);
+        Self::with_cards(hand, library)
+    }
 
-        let mut hand = Vec::new();
-        for _ in 0..7
-        {
-            if let Some(card) = library.pop()
-            {
-                hand.push(card);
-            }
-        }
+    /// Unshuffled: the first seven cards of the deck list are the opening
+    /// hand, in order, and the rest are drawn in list order.
+    pub fn stacked(deck: &Deck) -> Self
+    {
+        let mut hand = deck.cards.clone();
+        let mut library = hand.split_off(hand.len().min(7));
+        library.reverse();
+        Self::with_cards(hand, library)
+    }
 
+    fn with_cards(hand: Vec<Card>, library: Vec<Card>) -> Self
+    {
         let mut zones = HashMap::new();
         zones.insert(Zone::Library, library);
         zones.insert(Zone::Hand, hand);
@@ -94,8 +148,34 @@ impl Player
         {
             life: 20,
             zones,
+            lands_played_this_turn: 0,
+            land_drops_per_turn: default_land_drops_per_turn(),
+            extra_land_drops: 0,
+            max_hand_size: default_max_hand_size(),
         }
     }
+
+    pub fn land_drops_remaining(&self) -> u32
+    {
+        (self.land_drops_per_turn + self.extra_land_drops).saturating_sub(self.lands_played_this_turn)
+    }
+
+    pub fn can_play_land(&self) -> bool
+    {
+        self.land_drops_remaining() > 0
+    }
+
+    /// Allow additional land plays this turn, e.g. from Explore-style effects.
+    pub fn grant_extra_land_drops(&mut self, count: u32)
+    {
+        self.extra_land_drops += count;
+    }
+
+    pub fn reset_land_drops(&mut self)
+    {
+        self.lands_played_this_turn = 0;
+        self.extra_land_drops = 0;
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -107,29 +187,102 @@ pub struct GameState
     pub step: GameStep,
     pub attacking_creatures: Vec<usize>, // indices of creatures on battlefield that are attacking
     pub blocking_map: HashMap<usize, usize>, // maps blocker index to attacker index
+    #[serde(default)]
+    pub events: Vec<GameEvent>,
+    #[serde(default)]
+    pub next_link_id: u32,
+    #[serde(default)]
+    pub turn_queue: TurnQueue,
+    #[serde(default)]
+    pub decked_player: Option<usize>,   // lost by drawing from an empty library
+    #[serde(skip, default = "StdRng::from_entropy")]
+    pub rng: StdRng,
+    #[serde(skip)]
+    pub controllers: Vec<Box<dyn PlayerController>>,   // one per seat; see controller.rs
+    #[serde(skip)]
+    pub mulligan_strategies: Vec<Option<Box<dyn MulliganStrategy>>>, // None leaves mulligans to the controller
 }
 
 impl GameState 
 {
     pub fn new(player_count: usize, deck: &Deck) -> Self 
     {
-        let mut players = Vec::new();
-        for _ in 0..player_count.max(2) // Minimum 2 players
-        {
-            players.push(Player::new(deck));
-        }
+        Self::with_rng(player_count, deck, StdRng::from_entropy())
+    }
+
+    /// Deterministic game: the same seed and deck always produce the same game.
+    pub fn new_seeded(player_count: usize, deck: &Deck, seed: u64) -> Self
+    {
+        Self::with_rng(player_count, deck, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(player_count: usize, deck: &Deck, rng: StdRng) -> Self
+    {
+        let decks = vec![deck; player_count.max(2)]; // Minimum 2 players
+        Self::with_decks(&decks, 0, rng)
+    }
+
+    /// One deck per player, e.g. for matches between two different decks.
+    pub fn new_with_decks(decks: &[&Deck], starting_player: usize, seed: u64) -> Self
+    {
+        Self::with_decks(decks, starting_player, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_decks(decks: &[&Deck], starting_player: usize, mut rng: StdRng) -> Self
+    {
+        let players: Vec<Player> = decks.iter().map(|deck| Player::new(deck, &mut rng)).collect();
+        Self::with_players(players, starting_player, rng)
+    }
 
+    /// Unshuffled decks (see [`Player::stacked`]) for rules tests that need
+    /// to know exactly which cards are where.
+    pub fn new_stacked(decks: &[&Deck], starting_player: usize) -> Self
+    {
+        let players = decks.iter().map(|deck| Player::stacked(deck)).collect();
+        Self::with_players(players, starting_player, StdRng::seed_from_u64(0))
+    }
+
+    fn with_players(players: Vec<Player>, starting_player: usize, rng: StdRng) -> Self
+    {
+        let seats = players.len();
         GameState
         {
+            current_player_index: starting_player % players.len(),
             players,
-            current_player_index: 0,
             turns: 0,
             step: GameStep::StartTurn,
             attacking_creatures: Vec::new(),
             blocking_map: HashMap::new(),
+            events: Vec::new(),
+            next_link_id: 0,
+            turn_queue: TurnQueue::default(),
+            decked_player: None,
+            rng,
+            controllers: (0..seats).map(|_| default_controller()).collect(),
+            mulligan_strategies: vec![None; seats],
         }
     }
 
+    /// The player who won, once the game is over. None for a draw or an unfinished game.
+    pub fn winner(&self) -> Option<usize>
+    {
+        if !self.is_game_over()
+        {
+            return None;
+        }
+
+        let alive: Vec<usize> = (0..self.players.len())
+            .filter(|&i| self.players[i].life > 0 && self.decked_player != Some(i))
+            .collect();
+        if alive.len() == 1 { Some(alive[0]) } else { None }
+    }
+
+    /// The player being attacked this combat.
+    pub fn defending_player(&self) -> usize
+    {
+        (self.current_player_index + 1) % self.players.len()
+    }
+
     pub fn new_default() -> Self {
         let deck = Deck::example();
         Self::new(2, &deck) // Default 2 players
@@ -174,17 +327,365 @@ impl GameState
     pub fn set_life(&mut self, life: i32) {
         self.current_player_mut().life = life;
     }
+
+    pub fn emit(&mut self, event: GameEvent)
+    {
+        vlog!(ELoggingVerbosity::VeryVerbose, "{:?}", event);
+        self.events.push(event);
+    }
+
+    /// Move a land from the current player's hand to the battlefield if they
+    /// still have a land drop available. Returns false if the play is illegal.
+    pub fn play_land(&mut self, hand_index: usize) -> bool
+    {
+        self.play_land_from(Zone::Hand, hand_index)
+    }
+
+    pub fn play_land_from_exile(&mut self, exile_index: usize) -> bool
+    {
+        self.play_land_from(Zone::Exile, exile_index)
+    }
+
+    pub(crate) fn play_land_from(&mut self, zone: Zone, index: usize) -> bool
+    {
+        if self.step != GameStep::Main || !self.current_player().can_play_land()
+        {
+            return false;
+        }
+
+        let mut card =
+        {
+            let (player, turns) = (self.current_player_index, self.turns);
+            let cards = self.zones_mut().get_mut(&zone).unwrap();
+            if index >= cards.len()
+                || !cards[index].is_type(crate::card::CardType::Land)
+                || (zone == Zone::Exile && !crate::exile::is_playable_from_exile(&cards[index], player, turns))
+            {
+                return false;
+            }
+            cards.remove(index)
+        };
+
+        crate::exile::clear_exiled(&mut card);
+        vlog!(ELoggingVerbosity::Verbose, "Play {}", card.name);
+
+        self.current_player_mut().lands_played_this_turn += 1;
+        let player = self.current_player_index;
+        self.enter_battlefield(player, card);
+        true
+    }
+
+    /// Cast a creature from the current player's hand, tapping untapped lands
+    /// to pay for it. Returns false if the card can't be cast right now.
+    pub fn cast(&mut self, hand_index: usize) -> bool
+    {
+        self.cast_from(Zone::Hand, hand_index)
+    }
+
+    pub fn cast_from_exile(&mut self, exile_index: usize) -> bool
+    {
+        self.cast_from(Zone::Exile, exile_index)
+    }
+
+    fn cast_from(&mut self, zone: Zone, index: usize) -> bool
+    {
+        let player = self.current_player_index;
+        let Some(cost) = self.zones().get(&zone).unwrap().get(index).map(|c| crate::discard::cost_in_zone(c, zone)) else { return false; };
+        match self.default_payment(player, cost, None)
+        {
+            Some(payment) => self.cast_paid(zone, index, &payment),
+            None => false,
+        }
+    }
+
+    /// Cast tapping exactly the lands at `payment` (battlefield indices).
+    pub(crate) fn cast_paid(&mut self, zone: Zone, index: usize, payment: &[usize]) -> bool
+    {
+        if self.step != GameStep::Main
+        {
+            return false;
+        }
+
+        let (player, turns) = (self.current_player_index, self.turns);
+        {
+            let cards = self.zones().get(&zone).unwrap();
+            if index >= cards.len()
+                || !crate::creature::is_creature(&cards[index])
+                || !self.valid_payment(player, payment, crate::discard::cost_in_zone(&cards[index], zone), None)
+                || (zone == Zone::Exile && !crate::exile::is_playable_from_exile(&cards[index], player, turns))
+            {
+                return false;
+            }
+        }
+        let mut card = self.zones_mut().get_mut(&zone).unwrap().remove(index);
+
+        crate::exile::clear_exiled(&mut card);
+        vlog!(ELoggingVerbosity::Verbose, "Cast {}", card.name);
+
+        // Tap the lands used to pay for the creature's cost
+        {
+            let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
+            for &i in payment
+            {
+                crate::tappable::set_tapped(&mut battlefield[i], true);
+            }
+        }
+
+        self.enter_battlefield(player, card);
+        true
+    }
+
+    /// Put `card` onto `player`'s battlefield: creatures arrive with summoning
+    /// sickness and enters-the-battlefield triggers resolve.
+    pub(crate) fn enter_battlefield(&mut self, player: usize, mut card: Card)
+    {
+        crate::creature::set_summoning_sickness(&mut card, true);
+        let etb = crate::ability::triggered_effects(&card, Trigger::EntersBattlefield);
+        self.players[player].zones.get_mut(&Zone::Battlefield).unwrap().push(card);
+        self.resolve_effects(player, &etb);
+    }
+
+    /// Count available untapped lands as available mana
+    pub fn available_mana(&self) -> u32
+    {
+        self.zones().get(&Zone::Battlefield).unwrap().iter().filter(|card| 
+            card.is_type(crate::card::CardType::Land) && !crate::tappable::is_tapped(card)).count() as u32
+    }
+
+    /// London mulligan before the first turn: each player may shuffle their
+    /// hand away and draw seven again, then bottoms one card per mulligan.
+    pub fn resolve_mulligans(&mut self)
+    {
+        for player in 0..self.players.len()
+        {
+            let mut mulligans = 0;
+            while (mulligans as usize) < OPENING_HAND_SIZE
+                && !self.ask_mulligan(player, |s, g| s.keep(g, player, mulligans), |c, g| c.keep_hand(g, player, mulligans))
+            {
+                mulligans += 1;
+                vlog!(ELoggingVerbosity::Verbose, "Player {} mulligans ({})", player + 1, mulligans);
+                self.emit(GameEvent::Mulliganed { player, mulligans });
+                self.redeal_hand(player);
+            }
+
+            let count = (mulligans as usize).min(self.players[player].zones[&Zone::Hand].len());
+            if count == 0
+            {
+                continue;
+            }
+
+            let chosen = self.ask_mulligan(player, |s, g| s.bottom(g, player, count), |c, g| c.cards_to_bottom(g, player, count));
+            self.bottom_from_hand(player, chosen, count);
+        }
+    }
+
+    /// Have `strategy` take `player`'s mulligan decisions instead of their controller.
+    pub fn set_mulligan_strategy(&mut self, player: usize, strategy: Box<dyn MulliganStrategy>)
+    {
+        if self.mulligan_strategies.len() <= player
+        {
+            self.mulligan_strategies.resize_with(player + 1, || None);
+        }
+        self.mulligan_strategies[player] = Some(strategy);
+    }
+
+    // Ask the seat's mulligan strategy if it has one, otherwise its controller
+    fn ask_mulligan<R>(
+        &mut self,
+        player: usize,
+        by_strategy: impl FnOnce(&mut dyn MulliganStrategy, &GameState) -> R,
+        by_controller: impl FnOnce(&mut dyn PlayerController, &GameState) -> R,
+    ) -> R
+    {
+        match self.mulligan_strategies.get_mut(player).and_then(Option::take)
+        {
+            Some(mut strategy) =>
+            {
+                let result = by_strategy(strategy.as_mut(), self);
+                self.mulligan_strategies[player] = Some(strategy);
+                result
+            }
+            None => self.ask(player, by_controller),
+        }
+    }
+
+    /// Shuffle `player`'s hand into their library and draw a new opening hand.
+    pub fn redeal_hand(&mut self, player: usize)
+    {
+        let zones = &mut self.players[player].zones;
+        let hand = std::mem::take(zones.get_mut(&Zone::Hand).unwrap());
+        let library = zones.get_mut(&Zone::Library).unwrap();
+        library.extend(hand);
+        library.shuffle(&mut self.rng);
+        let hand = draw_opening_hand(library);
+        zones.insert(Zone::Hand, hand);
+    }
+
+    /// Put exactly `count` of `player`'s hand on the bottom of their library,
+    /// preferring the `chosen` indices.
+    pub fn bottom_from_hand(&mut self, player: usize, mut chosen: Vec<usize>, count: usize)
+    {
+        let hand_size = self.players[player].zones[&Zone::Hand].len();
+        chosen.retain(|&i| i < hand_size);
+        chosen.sort_unstable();
+        chosen.dedup();
+        // Top up from the end of the hand if the controller picked too few
+        for i in (0..hand_size).rev()
+        {
+            if chosen.len() >= count
+            {
+                break;
+            }
+            if !chosen.contains(&i)
+            {
+                chosen.push(i);
+            }
+        }
+        chosen.truncate(count);
+        chosen.sort_unstable_by(|a, b| b.cmp(a));
+
+        let zones = &mut self.players[player].zones;
+        for index in chosen
+        {
+            let card = zones.get_mut(&Zone::Hand).unwrap().remove(index);
+            zones.get_mut(&Zone::Library).unwrap().insert(0, card);
+        }
+    }
+
+    /// Built-in decision maker: play a land while drops remain, then cast the
+    /// first creature that can be paid for. Cards playable from exile are
+    /// used before cards in hand. Returns None to pass.
+    pub fn autopilot_action(&self) -> Option<Action>
+    {
+        let player = self.current_player_index;
+        let hand = self.zones().get(&Zone::Hand).unwrap();
+        let exile = self.zones().get(&Zone::Exile).unwrap();
+        let playable_exile: Vec<usize> = crate::exile::playable_exile_indices(exile, self.current_player_index, self.turns);
+
+        if self.current_player().can_play_land()
+        {
+            if let Some(&pos) = playable_exile.iter().find(|&&i| exile[i].is_type(crate::card::CardType::Land))
+            {
+                return Some(Action::PlayLand { zone: Zone::Exile, index: pos });
+            }
+            if let Some(pos) = hand.iter().position(|c| c.is_type(crate::card::CardType::Land))
+            {
+                return Some(Action::PlayLand { zone: Zone::Hand, index: pos });
+            }
+        }
+
+        let available_mana = self.available_mana();
+        let castable = |card: &Card, zone: Zone| crate::creature::is_creature(card)
+            && crate::discard::cost_in_zone(card, zone) <= available_mana;
+        let cast = |zone: Zone, index: usize, card: &Card| self.default_payment(player, crate::discard::cost_in_zone(card, zone), None)
+            .map(|payment| Action::Cast { zone, index, payment });
+
+        if let Some(&pos) = playable_exile.iter().find(|&&i| castable(&exile[i], Zone::Exile))
+        {
+            return cast(Zone::Exile, pos, &exile[pos]);
+        }
+        hand.iter().position(|c| castable(c, Zone::Hand)).and_then(|pos| cast(Zone::Hand, pos, &hand[pos]))
+    }
+
+    /// Declare `attackers` (already checked), tap them and fire attack triggers.
+    pub(crate) fn commit_attackers(&mut self, attackers: Vec<usize>)
+    {
+        let effects: Vec<Effect> =
+        {
+            let battlefield = self.zones().get(&Zone::Battlefield).unwrap();
+            attackers.iter()
+                .flat_map(|&i| crate::ability::triggered_effects(&battlefield[i], Trigger::Attacks))
+                .collect()
+        };
+        self.attacking_creatures = attackers;
+
+        // Tap all attacking creatures, except those with vigilance
+        let attacking_to_tap = self.attacking_creatures.clone();
+        {
+            let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
+            for idx in attacking_to_tap {
+                if idx < battlefield.len() && !battlefield[idx].has_keyword("vigilance") {
+                    crate::tappable::set_tapped(&mut battlefield[idx], true);
+                }
+            }
+        }
+
+        let player = self.current_player_index;
+        self.resolve_effects(player, &effects);
+
+        if !self.is_game_over()
+        {
+            self.step = GameStep::DeclareBlockers;
+        }
+    }
+
+    /// Record blocks (blocker -> attacker), keeping only legal ones: each
+    /// attacker takes at most one blocker.
+    pub(crate) fn commit_blockers(&mut self, blocks: HashMap<usize, usize>)
+    {
+        let defender = self.defending_player();
+        self.blocking_map.clear();
+        let battlefield = self.players[defender].zones.get(&Zone::Battlefield).unwrap();
+        let mut blocks: Vec<(usize, usize)> = blocks.into_iter().collect();
+        blocks.sort_unstable();
+        for (blocker, attacker) in blocks
+        {
+            if blocker < battlefield.len()
+                && crate::controller::can_block(&battlefield[blocker])
+                && self.attacking_creatures.contains(&attacker)
+                && !self.blocking_map.values().any(|&a| a == attacker)
+            {
+                self.blocking_map.insert(blocker, attacker);
+            }
+        }
+
+        // Taking damage with mana up is a tell for anyone reading the table (see belief.rs)
+        let unblocked = self.attacking_creatures.iter().filter(|a| !self.blocking_map.values().any(|b| b == *a)).count() as u32;
+        if unblocked > 0
+        {
+            let idle_blockers = battlefield.iter().enumerate()
+                .filter(|(i, c)| crate::controller::can_block(c) && !self.blocking_map.contains_key(i))
+                .count() as u32;
+            let untapped_mana = battlefield.iter()
+                .filter(|c| c.is_type(crate::card::CardType::Land) && !crate::tappable::is_tapped(c))
+                .count() as u32;
+            let hand_size = self.players[defender].zones[&Zone::Hand].len();
+            self.emit(GameEvent::DeclinedBlocks { player: defender, hand_size, untapped_mana, unblocked, idle_blockers });
+        }
+        self.step = GameStep::AssignDamage;
+    }
 }
 
 impl GameState 
 {
     pub fn step(&mut self)
     {
+        if self.turn_queue.take_skip(self.current_player_index, self.step)
+        {
+            vlog!(ELoggingVerbosity::Verbose, "Skip {:?}", self.step);
+            self.step = match self.step
+            {
+                GameStep::Untap => GameStep::Upkeep,
+                GameStep::Upkeep => GameStep::Draw,
+                GameStep::Draw => GameStep::Main,
+                GameStep::Main => GameStep::DeclareAttackers,
+                // Skipping combat skips the whole combat phase
+                GameStep::DeclareAttackers | GameStep::DeclareBlockers | GameStep::AssignDamage => GameStep::EndTurn,
+                other => other,
+            };
+            return;
+        }
+
         match self.step
         {
             GameStep::StartTurn =>
             {
+                if self.turns == 0
+                {
+                    self.resolve_mulligans();
+                }
                 self.turns += 1;
+                self.current_player_mut().reset_land_drops();
                 self.step = GameStep::Untap;
             }
 
@@ -214,160 +715,92 @@ impl GameState
                     crate::creature::set_summoning_sickness(card, false);
                 }
 
-                self.step = GameStep::Draw;
+                // "At the beginning of your upkeep" triggers
+                let effects: Vec<Effect> = self.zones().get(&Zone::Battlefield).unwrap().iter()
+                    .flat_map(|c| crate::ability::triggered_effects(c, Trigger::Upkeep))
+                    .collect();
+                let player = self.current_player_index;
+                self.resolve_effects(player, &effects);
+
+                if !self.is_game_over()
+                {
+                    self.step = GameStep::Draw;
+                }
             }
 
             GameStep::Draw =>
             {
-                let card = 
-                {
-                    let library = self.zones_mut().get_mut(&Zone::Library).unwrap();
-                    library.pop()
-                };
-
-                if let Some(card) = card 
+                // Drawing from an empty library ends the game
+                if self.draw_cards(self.current_player_index, 1)
                 {
-                    let hand = self.zones_mut().get_mut(&Zone::Hand).unwrap();
-                    hand.push(card);
                     self.step = GameStep::Main;
-                } 
-                else 
-                {
-                    self.step = GameStep::GameOver;
                 }
             }
 
             GameStep::Main =>
             {
-                // Play up to one land
+                // Let the controller play lands and cast spells until it passes
+                let player = self.current_player_index;
+                while let Some(action) = self.ask(player, |c, g| c.main_action(g, player))
                 {
-                    let card_option =
+                    // Passing priority moves on to combat; stop asking for main-phase actions
+                    if self.apply_action(player, action).is_err() || self.step != GameStep::Main
                     {
-                        let hand = self.zones_mut().get_mut(&Zone::Hand).unwrap();
-                        if let Some(pos) = hand.iter().position(|c| c.is_type(crate::card::CardType::Land))
-                        {
-                            Some(hand.remove(pos))  // hand borrow ends here
-                        }
-                        else
-                        {
-                            None
-                        }
-                    };
-
-                    if let Some(card) = card_option
-                    {
-                        let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                        battlefield.push(card);
+                        break;
                     }
                 }
 
-                // Cast as many creatures as possible until there is no more mana
-                loop
+                // A triggered ability may have ended the game, or the
+                // controller may have passed through apply_action
+                if !self.is_game_over() && self.step == GameStep::Main
                 {
-                    // Count available untapped lands as available mana
-                    let available_mana = self.zones().get(&Zone::Battlefield).unwrap().iter().filter(|card| 
-                        card.is_type(crate::card::CardType::Land) && !crate::tappable::is_tapped(card)).count() as u32;
-
-                    // Find first castable creature in hand
-                    let cast_pos = 
-                    {
-                        let hand = self.zones().get(&Zone::Hand).unwrap();
-                        hand.iter().position(|card| crate::creature::is_creature(card) && card.cost <= available_mana)
-                    };
-
-                    if let Some(pos) = cast_pos
-                    {
-                        // Remove card first
-                        let mut card = 
-                        {
-                            let hand = self.zones_mut().get_mut(&Zone::Hand).unwrap();
-                            hand.remove(pos)
-                        };
-
-                        vlog!(ELoggingVerbosity::Verbose, "Cast {}", card.name);
-
-                        // Newly cast creatures have summoning sickness
-                        crate::creature::set_summoning_sickness(&mut card, true);
-
-                        // Tap lands to pay for the creature's cost
-                        let mut need = card.cost;
-                        {
-                            let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                            for b in battlefield.iter_mut().filter(|c| c.is_type(crate::card::CardType::Land) && !crate::tappable::is_tapped(c)) 
-                            {
-                                if need == 0 
-                                { 
-                                    break; 
-                                }
-                                crate::tappable::set_tapped(b, true);
-                                need -= 1;
-                            }
-                        }
-
-                        // Put the card onto the battlefield
-                        let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                        battlefield.push(card);
-                    }
-                    else
-                    {
-                        // Nothing more can be cast
-                        break;
-                    }
+                    self.end_main_phase(player);
                 }
-
-                self.step = GameStep::DeclareAttackers;
             }
 
             GameStep::DeclareAttackers =>
             {
-                // Auto-attack: select all untapped creatures without summoning sickness
-                let attacking_indices = {
-                    let battlefield = self.zones().get(&Zone::Battlefield).unwrap();
-                    let mut indices = Vec::new();
-                    for (i, card) in battlefield.iter().enumerate()
-                    {
-                        if card.is_type(crate::card::CardType::Creature) && 
-                           !crate::creature::has_summoning_sickness(card) && 
-                           !crate::tappable::is_tapped(card)
-                        {
-                            indices.push(i);
-                        }
-                    }
-                    indices
-                };
-
-                self.attacking_creatures = attacking_indices;
-
-                // Tap all attacking creatures
-                let attacking_to_tap = self.attacking_creatures.clone();
+                // Ask the controller, keeping only creatures that can attack
+                let player = self.current_player_index;
+                let mut attacking_indices = self.ask(player, |c, g| c.declare_attackers(g, player));
                 {
-                    let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                    for idx in attacking_to_tap {
-                        if idx < battlefield.len() {
-                            crate::tappable::set_tapped(&mut battlefield[idx], true);
-                        }
-                    }
+                    let battlefield = self.zones().get(&Zone::Battlefield).unwrap();
+                    attacking_indices.retain(|&i| i < battlefield.len() && crate::controller::can_attack(&battlefield[i]));
+                    attacking_indices.sort_unstable();
+                    attacking_indices.dedup();
                 }
 
-                self.step = GameStep::DeclareBlockers;
+                self.commit_attackers(attacking_indices);
             }
 
             GameStep::DeclareBlockers =>
             {
-                // For now, no blockers are declared automatically
-                // This phase is where interactive blocking would happen
-                self.blocking_map.clear();
-                self.step = GameStep::AssignDamage;
+                // The defending player's controller blocks
+                let defender = self.defending_player();
+                let attackers = self.attacking_creatures.clone();
+                let blocks = if attackers.is_empty()
+                {
+                    HashMap::new()
+                }
+                else
+                {
+                    self.ask(defender, |c, g| c.declare_blockers(g, defender, &attackers))
+                };
+
+                self.commit_blockers(blocks);
             }
 
             GameStep::AssignDamage =>
             {
                 let mut creatures_to_destroy = Vec::new();
+                let mut blockers_to_destroy = Vec::new();
                 let mut damage_to_apply = 0;
+                let defender = self.defending_player();
                 
                 // First pass: calculate damage
                 {
                     let battlefield = self.zones().get(&Zone::Battlefield).unwrap();
+                    let defending_battlefield = self.players[defender].zones.get(&Zone::Battlefield).unwrap();
                     
                     for attacker_idx in &self.attacking_creatures {
                         if *attacker_idx >= battlefield.len() {
@@ -384,17 +817,17 @@ impl GameState
                             .map(|(blocker, _)| *blocker);
 
                         if let Some(blocker_idx) = blocked_by {
-                            if blocker_idx < battlefield.len() {
+                            if blocker_idx < defending_battlefield.len() {
                                 // Attacker and blocker deal damage to each other
-                                let blocker_toughness = crate::creature::creature_stats(&battlefield[blocker_idx])
+                                let blocker_toughness = crate::creature::creature_stats(&defending_battlefield[blocker_idx])
                                     .map(|stats| stats.toughness as i32)
                                     .unwrap_or(0);
-                                let blocker_power = crate::creature::creature_stats(&battlefield[blocker_idx])
+                                let blocker_power = crate::creature::creature_stats(&defending_battlefield[blocker_idx])
                                     .map(|stats| stats.power as i32)
                                     .unwrap_or(0);
 
                                 if attacker_power >= blocker_toughness {
-                                    creatures_to_destroy.push(blocker_idx);
+                                    blockers_to_destroy.push(blocker_idx);
                                 }
                                 let attacker_toughness = crate::creature::creature_stats(&battlefield[*attacker_idx])
                                     .map(|stats| stats.toughness as i32)
@@ -419,23 +852,15 @@ impl GameState
                 creatures_to_destroy.sort_by(|a, b| b.cmp(a)); // Sort reverse to remove from end first
                 creatures_to_destroy.dedup();
                 
-                let destroyed_cards = {
-                    let battlefield = self.zones_mut().get_mut(&Zone::Battlefield).unwrap();
-                    let mut cards = Vec::new();
-                    for idx in creatures_to_destroy {
-                        if idx < battlefield.len() {
-                            cards.push(battlefield.remove(idx));
-                        }
-                    }
-                    cards
-                };
-
-                // Move destroyed cards to graveyard
-                {
-                    let graveyard = self.zones_mut().get_mut(&Zone::Graveyard).unwrap();
-                    for card in destroyed_cards {
-                        graveyard.push(card);
-                    }
+                blockers_to_destroy.sort_by(|a, b| b.cmp(a));
+                blockers_to_destroy.dedup();
+                
+                let current = self.current_player_index;
+                for idx in creatures_to_destroy {
+                    self.move_from_battlefield(current, idx, Zone::Graveyard);
+                }
+                for idx in blockers_to_destroy {
+                    self.move_from_battlefield(defender, idx, Zone::Graveyard);
                 }
 
                 // Clear attacking and blocking data
@@ -444,8 +869,11 @@ impl GameState
 
                 // Check if any player has lost
                 let anyone_dead = self.players.iter().any(|p| p.life <= 0);
-                if anyone_dead {
+                if anyone_dead || self.is_game_over() {
                     self.step = GameStep::GameOver;
+                } else if self.turn_queue.extra_combats > 0 {
+                    self.turn_queue.extra_combats -= 1;
+                    self.step = GameStep::DeclareAttackers;
                 } else {
                     self.step = GameStep::EndTurn;
                 }
@@ -453,8 +881,13 @@ impl GameState
 
             GameStep::EndTurn =>
             {
-                // Advance to next player
-                self.current_player_index = (self.current_player_index + 1) % self.players.len();
+                // Cleanup: discard down to maximum hand size
+                let current = self.current_player_index;
+                self.discard_to_hand_size(current);
+                self.expire_madness();
+
+                // Advance to next player, honouring any extra turns
+                self.current_player_index = self.turn_queue.next_player(self.current_player_index, self.players.len());
                 self.step = GameStep::StartTurn;
             }
 
@@ -465,6 +898,17 @@ impl GameState
         }
     }
 
+    /// Move on to combat, recording what `player` left unused for anyone
+    /// reading the table (see belief.rs).
+    pub(crate) fn end_main_phase(&mut self, player: usize)
+    {
+        let untapped_mana = self.available_mana();
+        let hand_size = self.players[player].zones[&Zone::Hand].len();
+        let land_drop = self.players[player].can_play_land();
+        self.emit(GameEvent::PassedMain { player, hand_size, untapped_mana, land_drop });
+        self.step = GameStep::DeclareAttackers;
+    }
+
     pub fn is_game_over(&self) -> bool
     {
         self.step == GameStep::GameOver
@@ -498,10 +942,10 @@ impl GameState
 
     pub fn describe_verbose(&self)
     {
-        for zone in &[Zone::Hand, Zone::Battlefield, Zone::Library, Zone::Graveyard]
+        for zone in &[Zone::Hand, Zone::Battlefield, Zone::Library, Zone::Graveyard, Zone::Exile]
         {
             let cards = self.zones().get(zone).unwrap();
-            if cards.is_empty() && (*zone == Zone::Battlefield || *zone == Zone::Graveyard)
+            if cards.is_empty() && (*zone == Zone::Battlefield || *zone == Zone::Graveyard || *zone == Zone::Exile)
             {
                 continue;
             }
@@ -605,6 +1049,22 @@ impl GameState
                         }
                     }
                 }
+                Zone::Exile =>
+                {
+                    // Face-down cards stay hidden; mark cards that can still be played
+                    for card in cards.iter()
+                    {
+                        let name = if crate::exile::is_face_down(card) { "Face-down card" } else { card.name.as_str() };
+                        if crate::exile::is_playable_from_exile(card, self.current_player_index, self.turns)
+                        {
+                            println!("  {} (playable)", name);
+                        }
+                        else
+                        {
+                            println!("  {}", name);
+                        }
+                    }
+                }
                 _ => {}
             }
         }
@@ -616,212 +1076,236 @@ mod tests
 {
     use super::*;
     use crate::card::{grizzly_bears, forest};
+    use crate::controller::{Decision, ScriptedController};
     use crate::creature;
 
-    #[test]
-    fn creature_without_sickness_deals_damage()
+    /// Player 0 holds `hand` with `battlefield` in play and draws `library`
+    /// front to back; player 1 has nothing but `opponent_library`.
+    fn laid_out(hand: Vec<Card>, battlefield: Vec<Card>, mut library: Vec<Card>, opponent_library: Vec<Card>) -> GameState
+    {
+        let deck = Deck { cards: hand, sideboard: Vec::new() };
+        let empty = Deck { cards: Vec::new(), sideboard: Vec::new() };
+        let mut gs = GameState::new_stacked(&[&deck, &empty], 0);
+        library.reverse();
+        gs.players[0].zones.insert(Zone::Battlefield, battlefield);
+        gs.players[0].zones.insert(Zone::Library, library);
+        gs.players[1].zones.insert(Zone::Library, opponent_library);
+        gs.turns = 1;
+        gs
+    }
+
+    fn play_until(gs: &mut GameState, turn: u32, step: GameStep)
+    {
+        while !(gs.is_game_over() || gs.turns == turn && gs.step == step)
+        {
+            gs.step();
+        }
+    }
+
+    fn ready_bears() -> Card
     {
-        let mut battlefield = Vec::new();
         let mut g = grizzly_bears();
-        creature::add_creature_fragment(&mut g, 2, 2);
         creature::set_summoning_sickness(&mut g, false);
-        battlefield.push(g);
+        g
+    }
 
-        let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Battlefield, battlefield);
-        zones.insert(Zone::Hand, Vec::new());
-        zones.insert(Zone::Library, Vec::new());
-        zones.insert(Zone::Graveyard, Vec::new());
+    #[test]
+    fn creature_without_sickness_deals_damage()
+    {
+        let mut gs = laid_out(Vec::new(), vec![ready_bears()], Vec::new(), Vec::new());
+        gs.set_controller(0, Box::new(ScriptedController::new([Decision::Act(Action::Attack(vec![0]))])));
+        gs.set_controller(1, Box::new(ScriptedController::new([])));
+        gs.step = GameStep::DeclareAttackers;
+
+        play_until(&mut gs, 1, GameStep::EndTurn);
+        assert_eq!(gs.players[1].life, 18);
+        assert_eq!(gs.controllers[0].remaining(), 0);
+    }
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Combat };
-        gs.step();
-        assert_eq!(gs.life, 18);
+    #[test]
+    fn vigilance_attackers_stay_untapped()
+    {
+        let mut watchful = ready_bears();
+        watchful.keywords.push(String::from("Vigilance"));
+        let mut gs = laid_out(Vec::new(), vec![watchful, ready_bears()], Vec::new(), Vec::new());
+        gs.set_controller(0, Box::new(ScriptedController::new([Decision::Act(Action::Attack(vec![0, 1]))])));
+        gs.set_controller(1, Box::new(ScriptedController::new([])));
+        gs.step = GameStep::DeclareAttackers;
+
+        play_until(&mut gs, 1, GameStep::EndTurn);
+        assert_eq!(gs.players[1].life, 16);
+        assert_eq!(gs.controllers[0].remaining(), 0);
+        let battlefield = &gs.players[0].zones[&Zone::Battlefield];
+        assert!(!crate::tappable::is_tapped(&battlefield[0]));
+        assert!(crate::tappable::is_tapped(&battlefield[1]));
     }
 
     #[test]
     fn creature_with_sickness_does_not_deal_damage()
     {
-        let mut battlefield = Vec::new();
-        let mut g = grizzly_bears();
-        creature::set_summoning_sickness(&mut g, true);
-        battlefield.push(g);
-
-        let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Battlefield, battlefield);
-        zones.insert(Zone::Hand, Vec::new());
-        zones.insert(Zone::Library, Vec::new());
-        zones.insert(Zone::Graveyard, Vec::new());
-
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Combat };
-        gs.step();
-        assert_eq!(gs.life, 20);
+        let mut bears = grizzly_bears();
+        creature::set_summoning_sickness(&mut bears, true);
+        let mut gs = laid_out(Vec::new(), vec![bears], Vec::new(), Vec::new());
+        gs.step = GameStep::DeclareAttackers;
+        assert!(!gs.legal_actions(0).contains(&Action::Attack(vec![0])));
+
+        play_until(&mut gs, 1, GameStep::EndTurn);
+        assert_eq!(gs.players[1].life, 20);
     }
 
     #[test]
     fn summoning_sickness_cleared_on_upkeep()
     {
-        let mut battlefield = Vec::new();
-        let mut g = grizzly_bears();
-        creature::set_summoning_sickness(&mut g, true);
-        battlefield.push(g);
-
-        let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Battlefield, battlefield);
-        zones.insert(Zone::Hand, Vec::new());
-        zones.insert(Zone::Library, Vec::new());
-        zones.insert(Zone::Graveyard, Vec::new());
-
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Upkeep };
+        let mut bears = grizzly_bears();
+        creature::set_summoning_sickness(&mut bears, true);
+        let mut gs = laid_out(Vec::new(), vec![bears], Vec::new(), Vec::new());
+        gs.step = GameStep::Upkeep;
         gs.step();
-        let bf = gs.zones.get(&Zone::Battlefield).unwrap();
-        assert!(!crate::creature::has_summoning_sickness(&bf[0]));
+
+        let bf = &gs.players[0].zones[&Zone::Battlefield];
+        assert!(!creature::has_summoning_sickness(&bf[0]));
     }
 
     #[test]
     fn play_one_land_if_available()
     {
-        let library = Vec::new();
-        let mut hand = Vec::new();
-        hand.push(forest());
-
-        let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Library, library);
-        zones.insert(Zone::Hand, hand);
-        zones.insert(Zone::Battlefield, Vec::new());
-        zones.insert(Zone::Graveyard, Vec::new());
-
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Main };
+        let mut gs = laid_out(vec![forest(), forest()], Vec::new(), Vec::new(), Vec::new());
+        gs.step = GameStep::Main;
         gs.step();
 
-        assert_eq!(gs.zones.get(&Zone::Battlefield).unwrap().len(), 1);
+        assert_eq!(gs.players[0].zones[&Zone::Battlefield].len(), 1);
+        assert_eq!(gs.players[0].zones[&Zone::Hand].len(), 1);
     }
 
     #[test]
     fn play_as_many_creatures_as_possible()
     {
-        // Start with 4 lands available and two creatures in hand (cost 2 each)
-        let mut hand = Vec::new();
-        hand.push(grizzly_bears());
-        hand.push(grizzly_bears());
-
-        let mut battlefield = Vec::new();
-        for _ in 0..4 
-        {
-            battlefield.push(forest());
-        }
-
-        let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Library, Vec::new());
-        zones.insert(Zone::Hand, hand);
-        zones.insert(Zone::Battlefield, battlefield);
-        zones.insert(Zone::Graveyard, Vec::new());
-
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Main };
+        // 4 lands pay for both creatures (cost 2 each)
+        let lands = (0..4).map(|_| forest()).collect();
+        let mut gs = laid_out(vec![grizzly_bears(), grizzly_bears()], lands, Vec::new(), Vec::new());
+        gs.step = GameStep::Main;
         gs.step();
 
-        // Only ONE creature should be cast per main phase (4 lands available, but can only cast 1 creature)
-        assert_eq!(gs.zones.get(&Zone::Battlefield).unwrap().len(), 5); // 4 lands + 1 creature
-        // Verify we have the 4 lands still on battlefield
-        assert_eq!(gs.zones.get(&Zone::Battlefield).unwrap().iter().filter(|c| c.is_type(crate::card::CardType::Land)).count(), 4);
-        // One creature should be in hand still
-        assert_eq!(gs.zones.get(&Zone::Hand).unwrap().len(), 1);
+        let bf = &gs.players[0].zones[&Zone::Battlefield];
+        assert_eq!(bf.len(), 6);
+        assert_eq!(bf.iter().filter(|c| c.is_type(crate::card::CardType::Land)).count(), 4);
+        assert!(gs.players[0].zones[&Zone::Hand].is_empty());
     }
 
     #[test]
     fn multi_turn_summoning_sickness_flow()
     {
-        // Hand: 2x Forest + Grizzly, Battlefield: 1x Forest (to give us 2 mana for grizzly)
-        // Library: 2x Forest (for subsequent draws)
-        // This ensures we can play another land and cast the grizzly in the first main phase
-        let mut hand = Vec::new();
-        hand.push(forest());
-        hand.push(forest());
-        hand.push(grizzly_bears());
-
-        let mut battlefield = Vec::new();
-        battlefield.push(forest());
-
-        let mut library = Vec::new();
-        library.push(forest());
-        library.push(forest());
-
-        let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Library, library);
-        zones.insert(Zone::Hand, hand);
-        zones.insert(Zone::Battlefield, battlefield);
-        zones.insert(Zone::Graveyard, Vec::new());
-
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::StartTurn };
-
-        // Turn 1: StartTurn -> Untap -> Upkeep -> Draw -> Main -> Combat
-        gs.step(); // StartTurn -> Untap
-        gs.step(); // Untap -> Upkeep
-        gs.step(); // Upkeep -> Draw (draws a forest)
-        gs.step(); // Draw -> Main
-        gs.step(); // Main -> Combat (plays 1 land, casts grizzly with 2 mana total, gives it summoning sickness)
-        gs.step(); // Combat should NOT deal damage because creature is sick
-        assert_eq!(gs.life, 20, "Creature with summoning sickness should not deal damage on the turn it was cast");
-
-        // Continue to EndTurn -> StartTurn -> Untap -> Upkeep (for turn 2)
-        gs.step(); // Combat -> EndTurn
-        gs.step(); // EndTurn -> StartTurn
-        gs.step(); // StartTurn -> Untap
-        gs.step(); // Untap -> Upkeep (clears sickness)
-
-        // Advance to Combat of second turn
-        gs.step(); // Upkeep -> Draw (draws another forest)
-        gs.step(); // Draw -> Main
-        gs.step(); // Main -> Combat
-        gs.step(); // Combat should now deal damage
-        assert!(gs.life < 20, "Creature should deal damage after sickness cleared on upkeep");
+        // One Forest in play, another in hand: Grizzly Bears comes down on turn 1
+        let mut gs = laid_out(vec![forest(), grizzly_bears()], vec![forest()], vec![forest(), forest()], vec![forest(), forest()]);
+        gs.step = GameStep::StartTurn;
+        gs.turns = 0;
+        gs.set_controller(0, Box::new(ScriptedController::new([
+            Decision::Act(Action::PlayLand { zone: Zone::Hand, index: 0 }),
+            Decision::Act(Action::Cast { zone: Zone::Hand, index: 0, payment: vec![0, 1] }),
+            Decision::Act(Action::PassPriority),
+            Decision::Act(Action::Attack(vec![])),                  // still summoning sick
+            Decision::Act(Action::PassPriority),                    // turn 3
+            Decision::Act(Action::Attack(vec![2])),
+        ])));
+        gs.set_controller(1, Box::new(ScriptedController::new([])));
+
+        play_until(&mut gs, 2, GameStep::StartTurn);
+        assert_eq!(gs.players[0].zones[&Zone::Battlefield].len(), 3);
+        assert_eq!(gs.players[1].life, 20, "Creature with summoning sickness should not deal damage on the turn it was cast");
+
+        play_until(&mut gs, 3, GameStep::EndTurn);
+        assert_eq!(gs.players[1].life, 18, "Creature should deal damage after sickness cleared on upkeep");
+        assert_eq!(gs.controllers[0].remaining(), 0);
     }
 
     #[test]
     fn casting_taps_forests_used_for_payment()
     {
-        // Battlefield: 2x Forest (untapped). Hand: Grizzly Bears (cost 2). Main phase.
-        let mut hand = Vec::new();
-        hand.push(grizzly_bears());
-
-        let mut battlefield = Vec::new();
-        battlefield.push(forest());
-        battlefield.push(forest());
-
-        let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Library, Vec::new());
-        zones.insert(Zone::Hand, hand);
-        zones.insert(Zone::Battlefield, battlefield);
-        zones.insert(Zone::Graveyard, Vec::new());
-
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Main };
+        let mut gs = laid_out(vec![grizzly_bears()], vec![forest(), forest()], Vec::new(), Vec::new());
+        gs.step = GameStep::Main;
         gs.step();
 
         // After casting, a grizzly should be on the battlefield and two forests should be tapped
-        let bf = gs.zones.get(&Zone::Battlefield).unwrap();
+        let bf = &gs.players[0].zones[&Zone::Battlefield];
+        assert_eq!(bf.len(), 3, "Just the two forests and the grizzly");
         assert_eq!(bf.iter().filter(|c| c.is_type(crate::card::CardType::Land)).count(), 2);
         assert_eq!(bf.iter().filter(|c| c.is_type(crate::card::CardType::Creature)).count(), 1);
         let tapped_lands = bf.iter().filter(|c| c.is_type(crate::card::CardType::Land) && crate::tappable::is_tapped(c)).count();
         assert_eq!(tapped_lands, 2, "Both forests used to pay should be tapped");
     }
 
+    #[test]
+    fn passing_priority_ends_the_main_phase()
+    {
+        let mut gs = laid_out(Vec::new(), vec![ready_bears()], Vec::new(), Vec::new());
+        gs.set_controller(0, Box::new(ScriptedController::new([Decision::Act(Action::PassPriority), Decision::Act(Action::Attack(vec![0]))])));
+        gs.step = GameStep::Main;
+        gs.step();
+
+        // The main phase ended on the pass, before the attack came up
+        assert_eq!(gs.step, GameStep::DeclareAttackers);
+        assert!(gs.attacking_creatures.is_empty());
+        assert_eq!(gs.controllers[0].remaining(), 1);
+    }
+
     #[test]
     fn untap_phase_clears_tapped_state()
     {
-        let mut battlefield = Vec::new();
         let mut f = forest();
         crate::tappable::set_tapped(&mut f, true);
-        battlefield.push(f);
+        let mut gs = laid_out(Vec::new(), vec![f], Vec::new(), Vec::new());
+        gs.step = GameStep::Untap;
+        gs.step();
 
-        let mut zones = std::collections::HashMap::new();
-        zones.insert(Zone::Battlefield, battlefield);
-        zones.insert(Zone::Hand, Vec::new());
-        zones.insert(Zone::Library, Vec::new());
-        zones.insert(Zone::Graveyard, Vec::new());
+        assert!(!crate::tappable::is_tapped(&gs.players[0].zones[&Zone::Battlefield][0]));
+    }
+
+    #[test]
+    fn land_drops_limited_by_allowance()
+    {
+        let mut gs = GameState::new_default();
+        gs.step = GameStep::Main;
+        {
+            let hand = gs.zones_mut().get_mut(&Zone::Hand).unwrap();
+            hand.clear();
+            hand.push(forest());
+            hand.push(forest());
+            hand.push(forest());
+        }
+
+        assert!(gs.play_land(0));
+        assert!(!gs.play_land(0), "only one land drop by default");
+
+        gs.current_player_mut().grant_extra_land_drops(1);
+        assert!(gs.play_land(0));
+        assert!(!gs.play_land(0));
+        assert_eq!(gs.zones().get(&Zone::Battlefield).unwrap().len(), 2);
 
-        let mut gs = GameState { zones, life: 20, turns: 0, step: GameStep::Untap };
+        // Extra drops and the played count reset at the start of the next turn
+        gs.step = GameStep::StartTurn;
         gs.step();
+        assert_eq!(gs.current_player().lands_played_this_turn, 0);
+        assert_eq!(gs.current_player().land_drops_remaining(), 1);
+    }
 
-        let bf = gs.zones.get(&Zone::Battlefield).unwrap();
-        assert!(!crate::tappable::is_tapped(&bf[0]));
+    #[test]
+    fn extra_turn_and_skipped_draw()
+    {
+        let mut gs = GameState::new_default();
+        gs.turn_queue.add_extra_turn(0);
+        gs.turn_queue.skip_next(0, GameStep::Draw);
+        gs.step = GameStep::EndTurn;
+        gs.step();
+        assert_eq!(gs.current_player_index, 0, "extra turn goes before the next player");
+
+        let hand_before = gs.zones().get(&Zone::Hand).unwrap().len();
+        gs.step = GameStep::Draw;
+        gs.step();
+        assert_eq!(gs.step, GameStep::Main);
+        assert_eq!(gs.zones().get(&Zone::Hand).unwrap().len(), hand_before);
+
+        gs.step = GameStep::EndTurn;
+        gs.step();
+        assert_eq!(gs.current_player_index, 1);
     }
 }
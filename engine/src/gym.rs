@@ -0,0 +1,466 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::action::{Action, ActionError};
+use crate::card::{Card, CardType, Deck};
+use crate::controller::{can_attack, can_block, HeuristicController, PlayerController};
+use crate::game::{GameState, GameStep, Zone};
+
+// Fixed-size encodings of what one player can see and do, plus a gym-style
+// environment around them. Cards past the slot limits are left out of the
+// observation and can't be chosen.
+
+pub const MAX_HAND: usize = 10;
+pub const MAX_BATTLEFIELD: usize = 16;
+pub const MAX_ABILITIES: usize = 2;
+
+const STEPS: [GameStep; 10] = [
+    GameStep::StartTurn, GameStep::Untap, GameStep::Upkeep, GameStep::Draw, GameStep::Main,
+    GameStep::DeclareAttackers, GameStep::DeclareBlockers, GameStep::AssignDamage, GameStep::EndTurn, GameStep::GameOver,
+];
+
+const HAND_FEATURES: usize = 6;        // present, land, creature, cost, power, toughness
+const PERMANENT_FEATURES: usize = 9;   // present, land, creature, power, toughness, tapped, sick, attacking, blocking
+const PLAYER_FEATURES: usize = 9;      // life, hand, library, graveyard, exile, battlefield, lands played, land drops left, hand limit
+const GLOBAL_FEATURES: usize = STEPS.len() + 3;   // step, our turn, deciding, turn number
+
+pub const OBSERVATION_SIZE: usize = GLOBAL_FEATURES
+    + 2 * PLAYER_FEATURES
+    + MAX_HAND * HAND_FEATURES
+    + 2 * MAX_BATTLEFIELD * PERMANENT_FEATURES;
+
+/// Everything an action index can mean. Hand and battlefield numbers are
+/// indices into the acting player's zones, except a block's attacker, which
+/// is on the attacking player's battlefield.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionSlot
+{
+    /// Pass in the main phase, or confirm the attackers or blockers chosen so far.
+    Pass,
+    PlayLand(usize),
+    Cast(usize),
+    Activate { permanent: usize, ability: usize },
+    /// Add a creature to the attack being declared.
+    Attacker(usize),
+    Block { blocker: usize, attacker: usize },
+}
+
+const PLAY_LAND: usize = 1;
+const CAST: usize = PLAY_LAND + MAX_HAND;
+const ACTIVATE: usize = CAST + MAX_HAND;
+const ATTACKER: usize = ACTIVATE + MAX_BATTLEFIELD * MAX_ABILITIES;
+const BLOCK: usize = ATTACKER + MAX_BATTLEFIELD;
+
+pub const ACTION_SPACE: usize = BLOCK + MAX_BATTLEFIELD * MAX_BATTLEFIELD;
+
+pub fn action_index(slot: ActionSlot) -> Option<usize>
+{
+    match slot
+    {
+        ActionSlot::Pass => Some(0),
+        ActionSlot::PlayLand(i) if i < MAX_HAND => Some(PLAY_LAND + i),
+        ActionSlot::Cast(i) if i < MAX_HAND => Some(CAST + i),
+        ActionSlot::Activate { permanent, ability } if permanent < MAX_BATTLEFIELD && ability < MAX_ABILITIES =>
+            Some(ACTIVATE + permanent * MAX_ABILITIES + ability),
+        ActionSlot::Attacker(i) if i < MAX_BATTLEFIELD => Some(ATTACKER + i),
+        ActionSlot::Block { blocker, attacker } if blocker < MAX_BATTLEFIELD && attacker < MAX_BATTLEFIELD =>
+            Some(BLOCK + blocker * MAX_BATTLEFIELD + attacker),
+        _ => None,
+    }
+}
+
+pub fn action_slot(index: usize) -> Option<ActionSlot>
+{
+    match index
+    {
+        0 => Some(ActionSlot::Pass),
+        i if i < CAST => Some(ActionSlot::PlayLand(i - PLAY_LAND)),
+        i if i < ACTIVATE => Some(ActionSlot::Cast(i - CAST)),
+        i if i < ATTACKER => Some(ActionSlot::Activate { permanent: (i - ACTIVATE) / MAX_ABILITIES, ability: (i - ACTIVATE) % MAX_ABILITIES }),
+        i if i < BLOCK => Some(ActionSlot::Attacker(i - ATTACKER)),
+        i if i < ACTION_SPACE => Some(ActionSlot::Block { blocker: (i - BLOCK) / MAX_BATTLEFIELD, attacker: (i - BLOCK) % MAX_BATTLEFIELD }),
+        _ => None,
+    }
+}
+
+/// The slot a main phase action from `legal_actions` fills. Plays from exile have none.
+fn main_phase_slot(action: &Action) -> Option<ActionSlot>
+{
+    match *action
+    {
+        Action::PlayLand { zone: Zone::Hand, index } => Some(ActionSlot::PlayLand(index)),
+        Action::Cast { zone: Zone::Hand, index, .. } => Some(ActionSlot::Cast(index)),
+        Action::ActivateAbility { permanent, ability, .. } => Some(ActionSlot::Activate { permanent, ability }),
+        Action::PassPriority => Some(ActionSlot::Pass),
+        _ => None,
+    }
+}
+
+fn stats(card: &Card) -> (f32, f32)
+{
+    crate::creature::creature_stats(card)
+        .map(|s| (s.power as f32, s.toughness as f32))
+        .unwrap_or((0.0, 0.0))
+}
+
+fn flag(value: bool) -> f32
+{
+    if value { 1.0 } else { 0.0 }
+}
+
+fn encode_player(features: &mut Vec<f32>, game: &GameState, player: usize)
+{
+    let p = &game.players[player];
+    let count = |zone: Zone| p.zones[&zone].len() as f32;
+    features.extend([
+        p.life as f32 / 20.0,
+        count(Zone::Hand) / MAX_HAND as f32,
+        count(Zone::Library) / 60.0,
+        count(Zone::Graveyard) / 60.0,
+        count(Zone::Exile) / 60.0,
+        count(Zone::Battlefield) / MAX_BATTLEFIELD as f32,
+        p.lands_played_this_turn as f32,
+        p.land_drops_remaining() as f32,
+        p.max_hand_size.map_or(0.0, |n| n as f32 / MAX_HAND as f32),
+    ]);
+}
+
+fn encode_battlefield(features: &mut Vec<f32>, game: &GameState, player: usize)
+{
+    let battlefield = &game.players[player].zones[&Zone::Battlefield];
+    let attacking = player == game.current_player_index;
+    for i in 0..MAX_BATTLEFIELD
+    {
+        match battlefield.get(i)
+        {
+            Some(card) =>
+            {
+                let (power, toughness) = stats(card);
+                features.extend([
+                    1.0,
+                    flag(card.is_type(CardType::Land)),
+                    flag(crate::creature::is_creature(card)),
+                    power / 10.0,
+                    toughness / 10.0,
+                    flag(crate::tappable::is_tapped(card)),
+                    flag(crate::creature::has_summoning_sickness(card)),
+                    flag(attacking && game.attacking_creatures.contains(&i)),
+                    flag(!attacking && game.blocking_map.contains_key(&i)),
+                ]);
+            }
+            None => features.extend([0.0; PERMANENT_FEATURES]),
+        }
+    }
+}
+
+/// `player`'s view of the game as [`OBSERVATION_SIZE`] numbers: the step,
+/// both players' public counts and counters, their own hand, and both
+/// battlefields. The opponent's hand and every library order stay hidden.
+pub fn encode_observation(game: &GameState, player: usize) -> Vec<f32>
+{
+    let opponent = (player + 1) % game.players.len();
+    let mut features = Vec::with_capacity(OBSERVATION_SIZE);
+
+    features.extend(STEPS.iter().map(|&s| flag(game.step == s)));
+    features.push(flag(game.current_player_index == player));
+    features.push(flag(!game.is_game_over() && game.deciding_player() == player));
+    features.push(game.turns as f32 / 50.0);
+
+    encode_player(&mut features, game, player);
+    encode_player(&mut features, game, opponent);
+
+    let hand = &game.players[player].zones[&Zone::Hand];
+    for i in 0..MAX_HAND
+    {
+        match hand.get(i)
+        {
+            Some(card) =>
+            {
+                let (power, toughness) = stats(card);
+                features.extend([
+                    1.0,
+                    flag(card.is_type(CardType::Land)),
+                    flag(crate::creature::is_creature(card)),
+                    card.cost as f32 / 10.0,
+                    power / 10.0,
+                    toughness / 10.0,
+                ]);
+            }
+            None => features.extend([0.0; HAND_FEATURES]),
+        }
+    }
+
+    encode_battlefield(&mut features, game, player);
+    encode_battlefield(&mut features, game, opponent);
+    features
+}
+
+/// Which of the [`ACTION_SPACE`] indices `player` may pick now. `attackers`
+/// and `blocks` are the choices already made while declaring combat.
+pub fn action_mask(game: &GameState, player: usize, attackers: &[usize], blocks: &[(usize, usize)]) -> Vec<bool>
+{
+    let mut mask = vec![false; ACTION_SPACE];
+    if game.is_game_over() || game.deciding_player() != player
+    {
+        return mask;
+    }
+
+    let mut allow = |slot: ActionSlot|
+    {
+        if let Some(index) = action_index(slot)
+        {
+            mask[index] = true;
+        }
+    };
+
+    let battlefield = &game.players[player].zones[&Zone::Battlefield];
+    match game.step
+    {
+        GameStep::Main =>
+        {
+            game.legal_actions(player).iter().filter_map(main_phase_slot).for_each(allow);
+        }
+        GameStep::DeclareAttackers =>
+        {
+            allow(ActionSlot::Pass);
+            (0..battlefield.len())
+                .filter(|i| !attackers.contains(i) && can_attack(&battlefield[*i]))
+                .for_each(|i| allow(ActionSlot::Attacker(i)));
+        }
+        GameStep::DeclareBlockers =>
+        {
+            allow(ActionSlot::Pass);
+            for blocker in (0..battlefield.len()).filter(|&b| can_block(&battlefield[b]) && !blocks.iter().any(|p| p.0 == b))
+            {
+                for &attacker in game.attacking_creatures.iter().filter(|&&a| !blocks.iter().any(|p| p.1 == a))
+                {
+                    allow(ActionSlot::Block { blocker, attacker });
+                }
+            }
+        }
+        _ => allow(ActionSlot::Pass),
+    }
+    mask
+}
+
+#[derive(Clone, Debug)]
+pub struct Observation
+{
+    pub features: Vec<f32>,
+    pub mask: Vec<bool>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StepResult
+{
+    pub observation: Observation,
+    pub reward: f32,   // 1 for a win, -1 for a loss, given when the episode ends
+    pub done: bool,
+}
+
+pub const AGENT: usize = 0;
+
+/// A two-player game where the agent plays seat 0 one action index at a
+/// time and `opponent` plays seat 1. Mulligans, targets and discards for the
+/// agent are left to the heuristic AI.
+pub struct GymEnv
+{
+    decks: [Deck; 2],
+    opponent: Box<dyn PlayerController>,
+    pub max_turns: u32,   // episodes past this are cut off with no reward
+    rng: StdRng,
+    game: GameState,
+    attackers: Vec<usize>,
+    blocks: Vec<(usize, usize)>,
+}
+
+impl GymEnv
+{
+    pub fn new(agent_deck: Deck, opponent_deck: Deck, opponent: Box<dyn PlayerController>, seed: u64) -> Self
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let game = GameState::new_with_decks(&[&agent_deck, &opponent_deck], 0, rng.r#gen());
+        GymEnv { decks: [agent_deck, opponent_deck], opponent, max_turns: 100, rng, game, attackers: Vec::new(), blocks: Vec::new() }
+    }
+
+    /// Start a new game, with a coin flip for who plays first, and run it
+    /// up to the agent's first decision.
+    pub fn reset(&mut self) -> Observation
+    {
+        let starting_player = self.rng.gen_range(0..2);
+        self.game = GameState::new_with_decks(&[&self.decks[0], &self.decks[1]], starting_player, self.rng.r#gen());
+        self.game.set_controller(AGENT, Box::new(HeuristicController));
+        self.game.set_controller(1 - AGENT, self.opponent.clone());
+        self.attackers.clear();
+        self.blocks.clear();
+        self.advance();
+        self.observe()
+    }
+
+    /// Take action `index`, then run the game to the agent's next decision or the end.
+    pub fn step(&mut self, index: usize) -> Result<StepResult, ActionError>
+    {
+        if self.is_done()
+        {
+            return Err(ActionError::GameOver);
+        }
+        if !self.mask().get(index).copied().unwrap_or(false)
+        {
+            return Err(ActionError::Illegal(format!("action {} is not legal now", index)));
+        }
+
+        self.take(index)?;
+        self.advance();
+
+        let done = self.is_done();
+        let reward = match self.game.winner()
+        {
+            Some(AGENT) => 1.0,
+            Some(_) => -1.0,
+            None => 0.0,
+        };
+        Ok(StepResult { observation: self.observe(), reward, done })
+    }
+
+    /// The agent's observation, with the attackers or blockers it has picked
+    /// so far marked on its battlefield.
+    pub fn observe(&self) -> Observation
+    {
+        let mut features = encode_observation(&self.game, AGENT);
+        let own_battlefield = GLOBAL_FEATURES + 2 * PLAYER_FEATURES + MAX_HAND * HAND_FEATURES;
+        let pending = self.attackers.iter().map(|&i| (i, 7)).chain(self.blocks.iter().map(|&(b, _)| (b, 8)));
+        for (i, feature) in pending.filter(|&(i, _)| i < MAX_BATTLEFIELD)
+        {
+            features[own_battlefield + i * PERMANENT_FEATURES + feature] = 1.0;
+        }
+        Observation { features, mask: self.mask() }
+    }
+
+    pub fn game(&self) -> &GameState
+    {
+        &self.game
+    }
+
+    pub fn is_done(&self) -> bool
+    {
+        self.game.is_game_over() || self.game.turns > self.max_turns
+    }
+
+    fn mask(&self) -> Vec<bool>
+    {
+        if self.is_done()
+        {
+            return vec![false; ACTION_SPACE];
+        }
+        action_mask(&self.game, AGENT, &self.attackers, &self.blocks)
+    }
+
+    fn take(&mut self, index: usize) -> Result<(), ActionError>
+    {
+        let slot = action_slot(index).expect("masked indices are in range");
+        let action = match (self.game.step, slot)
+        {
+            (GameStep::DeclareAttackers, ActionSlot::Attacker(i)) =>
+            {
+                self.attackers.push(i);
+                return Ok(());
+            }
+            (GameStep::DeclareBlockers, ActionSlot::Block { blocker, attacker }) =>
+            {
+                self.blocks.push((blocker, attacker));
+                return Ok(());
+            }
+            (GameStep::DeclareAttackers, ActionSlot::Pass) => Action::Attack(std::mem::take(&mut self.attackers)),
+            (GameStep::DeclareBlockers, ActionSlot::Pass) => Action::Block(std::mem::take(&mut self.blocks)),
+            _ => self.game.legal_actions(AGENT).into_iter()
+                .find(|a| main_phase_slot(a) == Some(slot))
+                .expect("masked main phase slots come from legal actions"),
+        };
+        self.game.apply_action(AGENT, action)
+    }
+
+    /// Whether the game is waiting on the agent for something the action space covers.
+    fn agent_decides(&self) -> bool
+    {
+        match self.game.step
+        {
+            GameStep::Main | GameStep::DeclareAttackers => self.game.current_player_index == AGENT,
+            GameStep::DeclareBlockers => self.game.defending_player() == AGENT && !self.game.attacking_creatures.is_empty(),
+            _ => false,
+        }
+    }
+
+    fn advance(&mut self)
+    {
+        while !self.is_done()
+        {
+            if self.agent_decides()
+            {
+                // Don't hand the agent decisions where passing is all it can do
+                let mask = self.mask();
+                if mask.iter().filter(|&&legal| legal).count() > 1
+                {
+                    return;
+                }
+                let _ = self.take(0);
+                continue;
+            }
+            self.game.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use rand::seq::IteratorRandom;
+
+    #[test]
+    fn action_slots_roundtrip()
+    {
+        for index in 0..ACTION_SPACE
+        {
+            assert_eq!(action_index(action_slot(index).unwrap()), Some(index));
+        }
+        assert_eq!(action_slot(ACTION_SPACE), None);
+        assert_eq!(action_index(ActionSlot::Cast(MAX_HAND)), None);
+    }
+
+    #[test]
+    fn random_agent_plays_full_episodes()
+    {
+        let mut env = GymEnv::new(Deck::example(), Deck::example(), Box::new(HeuristicController), 11);
+        let mut rng = StdRng::seed_from_u64(5);
+
+        for _ in 0..3
+        {
+            let mut observation = env.reset();
+            let mut result = None;
+            while !env.is_done()
+            {
+                assert_eq!(observation.features.len(), OBSERVATION_SIZE);
+                assert_eq!(observation.mask.len(), ACTION_SPACE);
+                let index = (0..ACTION_SPACE).filter(|&i| observation.mask[i]).choose(&mut rng).expect("some legal action");
+                let step = env.step(index).unwrap();
+                observation = step.observation;
+                result = Some((step.reward, step.done));
+            }
+
+            let (reward, done) = result.unwrap();
+            assert!(done);
+            assert!(observation.mask.iter().all(|&legal| !legal));
+            assert_eq!(reward, match env.game().winner() { Some(AGENT) => 1.0, Some(_) => -1.0, None => 0.0 });
+            assert!(env.step(0).is_err());
+        }
+    }
+
+    #[test]
+    fn illegal_index_is_rejected()
+    {
+        let mut env = GymEnv::new(Deck::example(), Deck::example(), Box::new(HeuristicController), 3);
+        let observation = env.reset();
+        let illegal = (0..ACTION_SPACE).find(|&i| !observation.mask[i]).unwrap();
+        assert!(matches!(env.step(illegal), Err(ActionError::Illegal(_))));
+    }
+}
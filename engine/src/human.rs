@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::ability::Target;
+use crate::action::Action;
+use crate::card::{Card, Deck};
+use crate::controller::{can_attack, can_block, library_top, HeuristicController, PlayerController};
+use crate::game::{GameState, GameStep, Zone};
+
+// A seat played from the terminal. Everything the player is shown is
+// numbered from 1; the helpers turn those numbers back into indices.
+
+/// A card's name with power and toughness for creatures, e.g. "Grizzly Bears 2/2".
+pub fn card_label(card: &Card) -> String
+{
+    match crate::creature::creature_stats(card)
+    {
+        Some(stats) => format!("{} {}/{}", card.name, stats.power, stats.toughness),
+        None => card.name.clone(),
+    }
+}
+
+/// A card on the battlefield, e.g. "Grizzly Bears 2/2 (sick, tapped)".
+pub fn permanent_label(card: &Card) -> String
+{
+    let mut label = card_label(card);
+    let mut notes = Vec::new();
+    if crate::creature::is_creature(card) && crate::creature::has_summoning_sickness(card)
+    {
+        notes.push("sick");
+    }
+    if crate::tappable::is_tapped(card)
+    {
+        notes.push("tapped");
+    }
+    if !notes.is_empty()
+    {
+        label += &format!(" ({})", notes.join(", "));
+    }
+    label
+}
+
+fn numbered(cards: &[Card], label: fn(&Card) -> String) -> String
+{
+    cards.iter().enumerate()
+        .map(|(i, c)| format!("  {}) {}\n", i + 1, label(c)))
+        .collect()
+}
+
+/// The table from `player`'s seat: every battlefield, their own hand, and
+/// only the sizes of hidden zones.
+pub fn seat_view(game: &GameState, player: usize) -> String
+{
+    let mut view = format!("--- Turn {}, {:?} ---\n", game.turns, game.step);
+    for p in (0..game.players.len()).filter(|&p| p != player)
+    {
+        let zones = &game.players[p].zones;
+        view += &format!("Player {}: {} life, {} cards in hand, {} in library\n",
+            p + 1, game.players[p].life, zones[&Zone::Hand].len(), zones[&Zone::Library].len());
+        view += &numbered(&zones[&Zone::Battlefield], permanent_label);
+    }
+
+    let zones = &game.players[player].zones;
+    view += &format!("You: {} life, {} in library, {} in graveyard\n",
+        game.players[player].life, zones[&Zone::Library].len(), zones[&Zone::Graveyard].len());
+    view += &numbered(&zones[&Zone::Battlefield], permanent_label);
+    view += "Your hand:\n";
+    view += &numbered(&zones[&Zone::Hand], card_label);
+    view
+}
+
+/// What choosing `action` does, in words.
+pub fn action_label(game: &GameState, player: usize, action: &Action) -> String
+{
+    let zones = &game.players[player].zones;
+    let from = |zone: &Zone| if *zone == Zone::Hand { String::new() } else { format!(" from {:?}", zone).to_lowercase() };
+    match action
+    {
+        Action::PlayLand { zone, index } => format!("Play {}{}", zones[zone][*index].name, from(zone)),
+        Action::Cast { zone, index, payment } => format!("Cast {}{} (tap {} lands)", zones[zone][*index].name, from(zone), payment.len()),
+        Action::ActivateAbility { permanent, ability, .. } => format!("Activate {} ability {}", zones[&Zone::Battlefield][*permanent].name, ability + 1),
+        Action::Attack(attackers) => format!("Attack with {} creatures", attackers.len()),
+        Action::Block(blocks) => format!("Block with {} creatures", blocks.len()),
+        Action::PassPriority => "Pass".to_string(),
+    }
+}
+
+/// "1 3" -> [0, 2]. Blank input is an empty list; anything else that isn't
+/// a positive number is rejected.
+pub fn parse_numbers(input: &str) -> Option<Vec<usize>>
+{
+    input.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<usize>().ok().filter(|&n| n > 0).map(|n| n - 1))
+        .collect()
+}
+
+/// "1:2 3:1" -> [(0, 1), (2, 0)], as (blocker, attacker) pairs.
+pub fn parse_blocks(input: &str) -> Option<Vec<(usize, usize)>>
+{
+    input.split_whitespace()
+        .map(|pair|
+        {
+            let (blocker, attacker) = pair.split_once(':')?;
+            let blocker = blocker.parse::<usize>().ok().filter(|&n| n > 0)?;
+            let attacker = attacker.parse::<usize>().ok().filter(|&n| n > 0)?;
+            Some((blocker - 1, attacker - 1))
+        })
+        .collect()
+}
+
+fn all_different(values: impl Iterator<Item = usize>) -> bool
+{
+    let mut values: Vec<usize> = values.collect();
+    let count = values.len();
+    values.sort_unstable();
+    values.dedup();
+    values.len() == count
+}
+
+/// Print `question` and read answers until `parse` accepts one. None once
+/// stdin is closed.
+fn ask<T>(question: &str, parse: impl Fn(&str) -> Option<T>) -> Option<T>
+{
+    loop
+    {
+        print!("{} > ", question);
+        io::stdout().flush().ok()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input).ok()? == 0
+        {
+            return None;
+        }
+        match parse(input.trim())
+        {
+            Some(answer) => return Some(answer),
+            None => println!("Didn't understand that."),
+        }
+    }
+}
+
+/// Show the top `count` cards of the library and ask which to put `elsewhere`.
+fn keep_on_top(game: &GameState, player: usize, count: usize, elsewhere: &str) -> Option<Vec<bool>>
+{
+    let top: Vec<Card> = library_top(game, player, count).cloned().collect();
+    println!("\nTop of your library:");
+    print!("{}", numbered(&top, card_label));
+    ask(&format!("Put which {}? (numbers, blank for none)", elsewhere),
+        |s| parse_numbers(s).filter(|picked| picked.iter().all(|&i| i < top.len()) && all_different(picked.iter().copied())))
+        .map(|picked| (0..top.len()).map(|i| !picked.contains(&i)).collect())
+}
+
+/// Asks the person at the terminal for every decision. If stdin closes the
+/// heuristic AI takes over.
+#[derive(Clone, Debug, Default)]
+pub struct HumanController;
+
+impl PlayerController for HumanController
+{
+    fn name(&self) -> &str
+    {
+        "human"
+    }
+
+    fn keep_hand(&mut self, game: &GameState, player: usize, mulligans: u32) -> bool
+    {
+        println!("\nOpening hand{}:", if mulligans > 0 { format!(" after {} mulligans", mulligans) } else { String::new() });
+        print!("{}", numbered(&game.players[player].zones[&Zone::Hand], card_label));
+        ask("Keep this hand? (y/n)", |s| match s { "y" | "yes" => Some(true), "n" | "no" => Some(false), _ => None })
+            .unwrap_or_else(|| HeuristicController.keep_hand(game, player, mulligans))
+    }
+
+    fn cards_to_bottom(&mut self, game: &GameState, player: usize, count: usize) -> Vec<usize>
+    {
+        let hand = &game.players[player].zones[&Zone::Hand];
+        print!("{}", numbered(hand, card_label));
+        ask(&format!("Choose {} cards to put on the bottom", count),
+            |s| parse_numbers(s).filter(|picked| picked.len() == count && picked.iter().all(|&i| i < hand.len()) && all_different(picked.iter().copied())))
+            .unwrap_or_else(|| HeuristicController.cards_to_bottom(game, player, count))
+    }
+
+    fn main_action(&mut self, game: &GameState, player: usize) -> Option<Action>
+    {
+        let actions = game.legal_actions(player);
+        if actions == [Action::PassPriority]
+        {
+            return None;
+        }
+
+        print!("\n{}", seat_view(game, player));
+        for (i, action) in actions.iter().enumerate()
+        {
+            println!("  [{}] {}", i + 1, action_label(game, player, action));
+        }
+
+        let choice = ask("Choose an action", |s| parse_numbers(s).filter(|n| n.len() == 1 && n[0] < actions.len()));
+        match choice
+        {
+            Some(n) => Some(actions[n[0]].clone()).filter(|a| *a != Action::PassPriority),
+            None => HeuristicController.main_action(game, player),
+        }
+    }
+
+    fn declare_attackers(&mut self, game: &GameState, player: usize) -> Vec<usize>
+    {
+        let battlefield = &game.players[player].zones[&Zone::Battlefield];
+        let ready: Vec<usize> = (0..battlefield.len()).filter(|&i| can_attack(&battlefield[i])).collect();
+        if ready.is_empty()
+        {
+            return Vec::new();
+        }
+
+        println!("\nCreatures that can attack:");
+        for (n, &i) in ready.iter().enumerate()
+        {
+            println!("  {}) {}", n + 1, permanent_label(&battlefield[i]));
+        }
+        ask("Attack with which? (numbers, blank for none)",
+            |s| parse_numbers(s).filter(|picked| picked.iter().all(|&n| n < ready.len()) && all_different(picked.iter().copied())))
+            .map(|picked| picked.into_iter().map(|n| ready[n]).collect())
+            .unwrap_or_else(|| HeuristicController.declare_attackers(game, player))
+    }
+
+    fn declare_blockers(&mut self, game: &GameState, player: usize, attackers: &[usize]) -> HashMap<usize, usize>
+    {
+        let mine = &game.players[player].zones[&Zone::Battlefield];
+        let theirs = &game.players[game.current_player_index].zones[&Zone::Battlefield];
+        let blockers: Vec<usize> = (0..mine.len()).filter(|&i| can_block(&mine[i])).collect();
+
+        println!("\nYou are attacked by:");
+        for (n, &a) in attackers.iter().enumerate()
+        {
+            println!("  {}) {}", n + 1, permanent_label(&theirs[a]));
+        }
+        if blockers.is_empty()
+        {
+            println!("You have no creatures that can block.");
+            return HashMap::new();
+        }
+        println!("Your creatures that can block:");
+        for (n, &b) in blockers.iter().enumerate()
+        {
+            println!("  {}) {}", n + 1, permanent_label(&mine[b]));
+        }
+
+        let valid = |pairs: &Vec<(usize, usize)>| pairs.iter().all(|&(b, a)| b < blockers.len() && a < attackers.len())
+            && all_different(pairs.iter().map(|p| p.0))
+            && all_different(pairs.iter().map(|p| p.1));
+        ask("Blocks as blocker:attacker pairs, e.g. 1:2 (blank for none)", |s| parse_blocks(s).filter(valid))
+            .map(|pairs| pairs.into_iter().map(|(b, a)| (blockers[b], attackers[a])).collect())
+            .unwrap_or_else(|| HeuristicController.declare_blockers(game, player, attackers))
+    }
+
+    fn choose_player_target(&mut self, game: &GameState, player: usize, target: Target) -> usize
+    {
+        println!("\nChoose a player to target:");
+        for p in 0..game.players.len()
+        {
+            let who = if p == player { "You".to_string() } else { format!("Player {}", p + 1) };
+            println!("  {}) {} ({} life)", p + 1, who, game.players[p].life);
+        }
+        ask("Target", |s| parse_numbers(s).filter(|n| n.len() == 1 && n[0] < game.players.len()))
+            .map(|n| n[0])
+            .unwrap_or_else(|| HeuristicController.choose_player_target(game, player, target))
+    }
+
+    fn choose_discard(&mut self, game: &GameState, player: usize) -> usize
+    {
+        let hand = &game.players[player].zones[&Zone::Hand];
+        println!("\nChoose a card to discard:");
+        print!("{}", numbered(hand, card_label));
+        ask("Discard", |s| parse_numbers(s).filter(|n| n.len() == 1 && n[0] < hand.len()))
+            .map(|n| n[0])
+            .unwrap_or_else(|| HeuristicController.choose_discard(game, player))
+    }
+
+    fn choose_scry(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        keep_on_top(game, player, count, "on the bottom")
+            .unwrap_or_else(|| HeuristicController.choose_scry(game, player, count))
+    }
+
+    fn choose_surveil(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        keep_on_top(game, player, count, "into your graveyard")
+            .unwrap_or_else(|| HeuristicController.choose_surveil(game, player, count))
+    }
+
+    fn box_clone(&self) -> Box<dyn PlayerController>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// Play `deck` from the terminal as player 1 against `opponent` piloting the same deck.
+pub fn play_vs_ai(deck: &Deck, opponent: Box<dyn PlayerController>)
+{
+    let mut game = GameState::new(2, deck);
+    game.set_controller(0, Box::new(HumanController));
+    game.set_controller(1, opponent);
+    println!("You are player 1, against the {} AI.", game.controller_name(1));
+
+    while !game.is_game_over()
+    {
+        let before = game.players[1].zones[&Zone::Battlefield].len();
+        let lives: Vec<i32> = game.players.iter().map(|p| p.life).collect();
+        let step = game.step;
+        game.step();
+
+        if step == GameStep::StartTurn
+        {
+            let whose = if game.current_player_index == 0 { "Your" } else { "Opponent's" };
+            println!("\n===== Turn {} ({} turn) =====", game.turns, whose);
+        }
+        if step == GameStep::Main && game.current_player_index == 1
+        {
+            for card in game.players[1].zones[&Zone::Battlefield].iter().skip(before)
+            {
+                println!("Opponent plays {}", card_label(card));
+            }
+        }
+        if step == GameStep::DeclareAttackers && game.current_player_index == 1 && game.attacking_creatures.is_empty()
+        {
+            println!("Opponent doesn't attack.");
+        }
+        for (p, life) in lives.iter().enumerate()
+        {
+            if game.players[p].life != *life
+            {
+                let who = if p == 0 { "You".to_string() } else { "Opponent".to_string() };
+                println!("{}: {} -> {} life", who, life, game.players[p].life);
+            }
+        }
+    }
+
+    match game.winner()
+    {
+        Some(0) => println!("\nYou win in {} turns!", game.turns),
+        Some(_) => println!("\nYou lose in {} turns.", game.turns),
+        None => println!("\nThe game is a draw."),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears};
+
+    #[test]
+    fn parses_numbers_and_blocks_one_based()
+    {
+        assert_eq!(parse_numbers("1 3"), Some(vec![0, 2]));
+        assert_eq!(parse_numbers("2,4"), Some(vec![1, 3]));
+        assert_eq!(parse_numbers(""), Some(vec![]));
+        assert_eq!(parse_numbers("0"), None);
+        assert_eq!(parse_numbers("x"), None);
+
+        assert_eq!(parse_blocks("1:2 3:1"), Some(vec![(0, 1), (2, 0)]));
+        assert_eq!(parse_blocks(""), Some(vec![]));
+        assert_eq!(parse_blocks("1-2"), None);
+    }
+
+    #[test]
+    fn seat_view_hides_the_opponents_hand()
+    {
+        let mut gs = GameState::new_stacked(&[&Deck { cards: vec![forest(), grizzly_bears()], sideboard: Vec::new() }, &Deck::example()], 0);
+        gs.players[1].zones.get_mut(&Zone::Battlefield).unwrap().push(grizzly_bears());
+
+        let view = seat_view(&gs, 0);
+        let their_hand = &gs.players[1].zones[&Zone::Hand];
+        assert!(view.contains(&format!("{} cards in hand", their_hand.len())));
+        assert!(view.contains("Your hand:\n  1) Forest\n  2) Grizzly Bears 2/2"));
+        assert_eq!(view.matches("Grizzly Bears").count(), 2, "our bears in hand and theirs in play only");
+    }
+}
@@ -42,14 +42,55 @@ macro_rules! vlog
     }};
 }
 
+pub mod ability;
+pub mod action;
+pub mod art;
+pub mod belief;
+pub mod booster;
 pub mod card;
+pub mod combat_ai;
+pub mod controller;
 pub mod creature;
+pub mod database;
+pub mod decklist;
+pub mod discard;
+pub mod event;
+pub mod exile;
 pub mod game;
+pub mod gym;
+pub mod human;
+pub mod library;
+pub mod match_play;
+pub mod mcts;
+pub mod mulligan;
 pub mod tappable;
+pub mod tournament;
+pub mod turn;
 pub mod sim;
 pub mod music;
+pub mod registry;
+pub mod scryfall;
+pub mod validator;
 
+pub use crate::ability::*;
+pub use crate::action::*;
+pub use crate::art::*;
+pub use crate::belief::*;
+pub use crate::booster::*;
 pub use crate::card::*;
+pub use crate::controller::*;
 pub use crate::creature::*;
+pub use crate::database::*;
+pub use crate::decklist::*;
+pub use crate::event::*;
 pub use crate::game::*;
+pub use crate::library::*;
+pub use crate::match_play::*;
+pub use crate::mcts::*;
+pub use crate::mulligan::*;
+pub use crate::registry::register_fragment;
+pub use crate::scryfall::*;
 pub use crate::sim::*;
+pub use crate::tournament::*;
+pub use crate::turn::*;
+pub use crate::validator::*;
@@ -0,0 +1,200 @@
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+
+use crate::card::Card;
+use crate::event::GameEvent;
+use crate::game::{GameState, Zone};
+
+// The top of a library is the end of its Vec, matching `pop()` in the Draw step.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LibraryPosition
+{
+    Top,
+    Bottom,
+}
+
+impl GameState
+{
+    fn library_mut(&mut self, player: usize) -> &mut Vec<Card>
+    {
+        self.players[player].zones.get_mut(&Zone::Library).unwrap()
+    }
+
+    pub fn shuffle_library(&mut self, player: usize)
+    {
+        let mut library = std::mem::take(self.library_mut(player));
+        library.shuffle(&mut self.rng);
+        *self.library_mut(player) = library;
+
+        self.emit(GameEvent::LibraryShuffled { player });
+    }
+
+    /// Find the first card matching `filter`, shuffle the library and move
+    /// the card to `destination`. Returns whether a card was found.
+    pub fn search_library<F>(&mut self, player: usize, filter: F, destination: Zone) -> bool
+    where
+        F: Fn(&Card) -> bool,
+    {
+        let found =
+        {
+            let library = self.library_mut(player);
+            library.iter().rposition(filter).map(|pos| library.remove(pos))
+        };
+
+        // A card searched into the hand (or back into the library) stays hidden
+        let public = !matches!(destination, Zone::Hand | Zone::Library);
+        let card = found.as_ref().filter(|_| public).map(|c| c.name.clone());
+        self.emit(GameEvent::LibrarySearched { player, found: found.is_some(), card });
+        self.shuffle_library(player);
+
+        let Some(card) = found else { return false; };
+        if destination == Zone::Battlefield
+        {
+            self.enter_battlefield(player, card);
+        }
+        else
+        {
+            self.players[player].zones.get_mut(&destination).unwrap().push(card);
+        }
+        true
+    }
+
+    /// Look at the top `count` cards; `keep_on_top` decides for each card
+    /// whether it stays on top or goes to the bottom. Kept cards retain their order.
+    pub fn scry<F>(&mut self, player: usize, count: usize, mut keep_on_top: F)
+    where
+        F: FnMut(&Card) -> bool,
+    {
+        let library = self.library_mut(player);
+        let split = library.len().saturating_sub(count);
+        let looked_at = library.split_off(split);
+
+        let (kept, bottom): (Vec<Card>, Vec<Card>) = looked_at.into_iter().partition(|c| keep_on_top(c));
+        let (kept_on_top, put_on_bottom) = (kept.len() as u32, bottom.len() as u32);
+
+        let library = self.library_mut(player);
+        library.splice(0..0, bottom);
+        library.extend(kept);
+
+        self.emit(GameEvent::Scried { player, kept_on_top, put_on_bottom });
+    }
+
+    /// Look at the top `count` cards; `keep_on_top` decides for each card
+    /// whether it stays on top or goes to the graveyard.
+    pub fn surveil<F>(&mut self, player: usize, count: usize, mut keep_on_top: F)
+    where
+        F: FnMut(&Card) -> bool,
+    {
+        let library = self.library_mut(player);
+        let split = library.len().saturating_sub(count);
+        let looked_at = library.split_off(split);
+
+        let (kept, binned): (Vec<Card>, Vec<Card>) = looked_at.into_iter().partition(|c| keep_on_top(c));
+        let (kept_on_top, put_in_graveyard) = (kept.len() as u32, binned.len() as u32);
+
+        self.library_mut(player).extend(kept);
+        self.players[player].zones.get_mut(&Zone::Graveyard).unwrap().extend(binned);
+
+        self.emit(GameEvent::Surveilled { player, kept_on_top, put_in_graveyard });
+    }
+
+    /// Put the top `count` cards into the graveyard. Returns how many were milled.
+    pub fn mill(&mut self, player: usize, count: usize) -> usize
+    {
+        let mut milled = 0;
+        for _ in 0..count
+        {
+            let Some(card) = self.library_mut(player).pop() else { break; };
+
+            self.emit(GameEvent::Milled { player, card: card.name.clone() });
+            self.players[player].zones.get_mut(&Zone::Graveyard).unwrap().push(card);
+            milled += 1;
+        }
+        milled
+    }
+
+    pub fn put_in_library(&mut self, player: usize, card: Card, position: LibraryPosition)
+    {
+        let event = GameEvent::PutInLibrary { player, card: card.name.clone(), position };
+        let library = self.library_mut(player);
+        match position
+        {
+            LibraryPosition::Top => library.push(card),
+            LibraryPosition::Bottom => library.insert(0, card),
+        }
+
+        self.emit(event);
+    }
+
+    /// Reveal the top `count` cards of `player`'s library to the players in `to`.
+    pub fn reveal_library_top(&mut self, player: usize, count: usize, to: &[usize])
+    {
+        let names: Vec<String> =
+        {
+            let library = self.players[player].zones.get(&Zone::Library).unwrap();
+            library.iter().rev().take(count).map(|c| c.name.clone()).collect()
+        };
+
+        for card in names
+        {
+            self.emit(GameEvent::Revealed { player, card, to: to.to_vec() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears};
+
+    fn state_with_library(cards: Vec<Card>) -> GameState
+    {
+        let mut gs = GameState::new_seeded(2, &crate::card::Deck::example(), 7);
+        *gs.library_mut(0) = cards;
+        gs.players[0].zones.get_mut(&Zone::Graveyard).unwrap().clear();
+        gs.players[0].zones.get_mut(&Zone::Hand).unwrap().clear();
+        gs.events.clear();
+        gs
+    }
+
+    #[test]
+    fn scry_and_surveil_move_unwanted_cards()
+    {
+        // Top of library is the end of the Vec: bears on top, then forest
+        let mut gs = state_with_library(vec![forest(), forest(), grizzly_bears()]);
+        gs.scry(0, 2, |c| c.name == "Forest");
+        let names: Vec<&str> = gs.players[0].zones[&Zone::Library].iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Grizzly Bears", "Forest", "Forest"]);
+
+        gs.surveil(0, 1, |_| false);
+        assert_eq!(gs.players[0].zones[&Zone::Graveyard].len(), 1);
+        assert_eq!(gs.events.last(), Some(&GameEvent::Surveilled { player: 0, kept_on_top: 0, put_in_graveyard: 1 }));
+    }
+
+    #[test]
+    fn searched_creature_enters_like_a_cast_one()
+    {
+        let mut gs = state_with_library(vec![forest(), grizzly_bears()]);
+        assert!(gs.search_library(0, crate::creature::is_creature, Zone::Battlefield));
+
+        assert!(gs.events.contains(&GameEvent::LibrarySearched { player: 0, found: true, card: Some(String::from("Grizzly Bears")) }));
+        let bears = gs.players[0].zones[&Zone::Battlefield].last().unwrap();
+        assert_eq!(bears.name, "Grizzly Bears");
+        assert!(crate::creature::has_summoning_sickness(bears));
+    }
+
+    #[test]
+    fn search_finds_card_and_mill_stops_at_empty_library()
+    {
+        let mut gs = state_with_library(vec![grizzly_bears(), forest(), grizzly_bears()]);
+        assert!(gs.search_library(0, |c| c.is_type(crate::card::CardType::Land), Zone::Hand));
+        assert_eq!(gs.players[0].zones[&Zone::Hand].len(), 1);
+        assert!(gs.events.contains(&GameEvent::LibrarySearched { player: 0, found: true, card: None }));
+        assert!(!gs.search_library(0, |c| c.is_type(crate::card::CardType::Land), Zone::Hand));
+
+        assert_eq!(gs.mill(0, 5), 2);
+        assert!(gs.players[0].zones[&Zone::Library].is_empty());
+    }
+}
@@ -1,7 +1,25 @@
 use engine::{set_global_verbosity, ELoggingVerbosity, game::ProgramState, game::StepCommand, sim, music::{MusicPlayer, MusicConfig, music_dir_path}};
+use engine::{default_controller, human, Deck, CardDatabase, Collation, CurveRequirement, LandRange, MctsConfig, MulliganStrategy, NoSideboarding, SimulatedKeep};
 use engine::vlog;
 use std::collections::HashMap;
 
+// Games per strategy for the "m" command
+const MULLIGAN_GAMES: u32 = 100;
+
+// The "b" command drafts the same pod every time so runs can be compared
+const DRAFT_SEED: u64 = 1;
+const DRAFT_GAMES: u32 = 500;
+
+// Best-of-three matches for the "w" command, seeded the same way every run
+const MATCHES: u32 = 200;
+const MATCH_SEED: u64 = 1;
+
+// Games against the MCTS reference for the "v" command
+const MCTS_GAMES: u32 = 20;
+
+// Seed for the "l" controller round-robin
+const TOURNAMENT_SEED: u64 = 1;
+
 fn main()
 {
     set_global_verbosity(ELoggingVerbosity::Normal);
@@ -25,6 +43,12 @@ fn main()
     println!("  g  -> run the current game to completion");
     println!("  d  -> run the simulation to completion for the current deck");
     println!("  r  -> run the whole simulation to completion (all decks)");
+    println!("  p  -> play a game yourself against the AI");
+    println!("  m  -> compare mulligan strategies for the current deck");
+    println!("  b  -> bot-draft a pod from the card data and simulate each deck");
+    println!("  w  -> match win rate of the current deck against the example deck");
+    println!("  v  -> win rate of the current deck against the example deck piloted by MCTS");
+    println!("  l  -> round-robin of the built-in controllers playing the current deck");
     println!("  q  -> quit");
     println!();
 
@@ -32,7 +56,64 @@ fn main()
     let mut current_nonlands = 32;
     let change_size = 1;
 
+    // Don't spend time optimizing a deck the format wouldn't allow
+    if !is_legal(&program_state, current_lands, current_nonlands)
+    {
+        return;
+    }
+
     program_state.step_mode = sim::parse_command(&read_line().trim());
+    if program_state.step_mode == StepCommand::Play
+    {
+        human::play_vs_ai(&Deck::example(), default_controller());
+        return;
+    }
+
+    if program_state.step_mode == StepCommand::Mulligans
+    {
+        let deck = sim::scenario_deck(current_lands, current_nonlands);
+        let strategies: [Box<dyn MulliganStrategy>; 3] = [Box::new(LandRange::default()), Box::new(CurveRequirement::default()), Box::new(SimulatedKeep::new(20, 0))];
+        for strategy in &strategies
+        {
+            sim::evaluate_mulligans(&deck, &Deck::example(), strategy.as_ref(), MULLIGAN_GAMES);
+        }
+        return;
+    }
+
+    if program_state.step_mode == StepCommand::Draft
+    {
+        match CardDatabase::load_dir(engine::card_data_dir_path())
+        {
+            Ok(db) =>
+            {
+                program_state.step_mode = StepCommand::RunAll;
+                sim::try_draft(&db, Collation::standard(None), DRAFT_SEED, DRAFT_GAMES, &mut program_state);
+            }
+            Err(e) => println!("Failed to load card data: {}", e),
+        }
+        return;
+    }
+
+    if program_state.step_mode == StepCommand::Matches
+    {
+        let deck = sim::scenario_deck(current_lands, current_nonlands);
+        sim::match_win_rate(&deck, &Deck::example(), MATCHES, MATCH_SEED, [&mut NoSideboarding, &mut NoSideboarding]);
+        return;
+    }
+
+    if program_state.step_mode == StepCommand::VsMcts
+    {
+        let deck = sim::scenario_deck(current_lands, current_nonlands);
+        sim::benchmark_vs_mcts(&deck, &Deck::example(), MCTS_GAMES, MctsConfig::default());
+        return;
+    }
+
+    if program_state.step_mode == StepCommand::Tournament
+    {
+        let deck = sim::scenario_deck(current_lands, current_nonlands);
+        sim::controller_tournament(&deck, TOURNAMENT_SEED);
+        return;
+    }
 
     // Hill-climbing algorithm: track results and find consensus among 3+ runs
     let mut result_history: HashMap<(u32, u32), Vec<f64>> = HashMap::new();
@@ -51,7 +132,7 @@ fn main()
         println!("Testing land/nonland ratios centered around {} lands, {} nonlands", current_lands, current_nonlands);
 
         // Test three configurations: current, +1 lands, -1 lands
-        let result0 = sim::try_scenario(current_lands, current_nonlands, &mut program_state);
+        let result0 = try_legal_scenario(current_lands, current_nonlands, &mut program_state);
         if program_state.step_mode == StepCommand::RunDeck
         {
             program_state.step_mode = sim::parse_command(&read_line().trim());
@@ -62,7 +143,7 @@ fn main()
             break;
         }
 
-        let result1 = sim::try_scenario(current_lands + change_size, current_nonlands - change_size, &mut program_state);
+        let result1 = try_legal_scenario(current_lands + change_size, current_nonlands - change_size, &mut program_state);
         if program_state.step_mode == StepCommand::RunDeck
         {
             program_state.step_mode = sim::parse_command(&read_line().trim());
@@ -73,7 +154,7 @@ fn main()
             break;
         }
 
-        let result2 = sim::try_scenario(current_lands - change_size, current_nonlands + change_size, &mut program_state);
+        let result2 = try_legal_scenario(current_lands - change_size, current_nonlands + change_size, &mut program_state);
         if program_state.step_mode == StepCommand::RunDeck
         {
             program_state.step_mode = sim::parse_command(&read_line().trim());
@@ -85,9 +166,17 @@ fn main()
         }
 
         // Track results
-        result_history.entry((current_lands, current_nonlands)).or_insert_with(Vec::new).push(result0);
-        result_history.entry((current_lands + change_size, current_nonlands - change_size)).or_insert_with(Vec::new).push(result1);
-        result_history.entry((current_lands - change_size, current_nonlands + change_size)).or_insert_with(Vec::new).push(result2);
+        for (key, result) in [
+            ((current_lands, current_nonlands), result0),
+            ((current_lands + change_size, current_nonlands - change_size), result1),
+            ((current_lands - change_size, current_nonlands + change_size), result2),
+        ]
+        {
+            if result.is_finite()
+            {
+                result_history.entry(key).or_insert_with(Vec::new).push(result);
+            }
+        }
 
         // Determine which configuration was best
         let smallest_turns_to_death = result0.min(result1).min(result2);
@@ -186,3 +275,29 @@ fn read_line() -> String
     io::stdin().read_line(&mut input).unwrap();
     input
 }
+
+/// Print the format violations of the scenario deck, if any. True when it's legal.
+fn is_legal(program_state: &ProgramState, lands: u32, nonlands: u32) -> bool
+{
+    let violations = program_state.validator.validate(&sim::scenario_deck(lands, nonlands));
+    if !violations.is_empty()
+    {
+        println!("Deck with {} lands and {} nonlands is not legal:", lands, nonlands);
+        for violation in &violations
+        {
+            println!("  {}", violation);
+        }
+    }
+    violations.is_empty()
+}
+
+/// Simulate the scenario deck unless the format forbids it, in which case it
+/// can never be the best (infinite turns to death).
+fn try_legal_scenario(lands: u32, nonlands: u32, program_state: &mut ProgramState) -> f64
+{
+    if !is_legal(program_state, lands, nonlands)
+    {
+        return f64::INFINITY;
+    }
+    sim::try_scenario(lands, nonlands, program_state)
+}
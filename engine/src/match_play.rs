@@ -0,0 +1,161 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Serialize, Deserialize};
+
+use crate::card::Deck;
+use crate::controller::{default_controller, PlayerController};
+use crate::game::GameState;
+
+// Games that run this long are called a draw
+const MAX_STEPS_PER_GAME: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameResult
+{
+    pub winner: Option<usize>,   // None for a draw
+    pub starting_player: usize,
+    pub turns: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MatchResult
+{
+    pub games: Vec<GameResult>,
+    pub winner: Option<usize>,
+}
+
+/// Chooses each player's deck between games of a match.
+pub trait SideboardStrategy
+{
+    /// Return the deck `player` uses for the next game. `deck` is the
+    /// registered deck (main and sideboard) and `results` the games so far.
+    fn sideboard(&mut self, player: usize, deck: &Deck, results: &[GameResult]) -> Deck;
+
+    /// Whether the loser of the previous game chooses to play first.
+    fn play_first(&mut self, _player: usize, _results: &[GameResult]) -> bool
+    {
+        true
+    }
+}
+
+/// Keeps the registered deck for every game.
+pub struct NoSideboarding;
+
+impl SideboardStrategy for NoSideboarding
+{
+    fn sideboard(&mut self, _player: usize, deck: &Deck, _results: &[GameResult]) -> Deck
+    {
+        deck.clone()
+    }
+}
+
+/// Best-of-N match between two decks; the loser of each game chooses who plays first in the next.
+pub struct Match
+{
+    pub decks: [Deck; 2],
+    pub games_to_win: u32,
+    pub controllers: [Box<dyn PlayerController>; 2],   // who plays each deck
+    rng: StdRng,
+}
+
+impl Match
+{
+    pub fn new(deck_a: Deck, deck_b: Deck) -> Self
+    {
+        Self::with_rng(deck_a, deck_b, StdRng::from_entropy())
+    }
+
+    pub fn new_seeded(deck_a: Deck, deck_b: Deck, seed: u64) -> Self
+    {
+        Self::with_rng(deck_a, deck_b, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(deck_a: Deck, deck_b: Deck, rng: StdRng) -> Self
+    {
+        Match { decks: [deck_a, deck_b], games_to_win: 2, controllers: [default_controller(), default_controller()], rng }
+    }
+
+    pub fn run(&mut self, strategies: [&mut dyn SideboardStrategy; 2]) -> MatchResult
+    {
+        let [first, second] = strategies;
+        let mut strategies = [first, second];
+        let mut results: Vec<GameResult> = Vec::new();
+        let mut wins = [0u32; 2];
+
+        // Coin flip for the first game
+        let mut starting_player = self.rng.gen_range(0..2);
+        let max_games = self.games_to_win * 2 + 1;
+
+        while wins[0] < self.games_to_win && wins[1] < self.games_to_win && (results.len() as u32) < max_games
+        {
+            let decks: Vec<Deck> = (0..2)
+                .map(|p| if results.is_empty() { self.decks[p].clone() } else { strategies[p].sideboard(p, &self.decks[p], &results) })
+                .collect();
+
+            let mut game = GameState::new_with_decks(&[&decks[0], &decks[1]], starting_player, self.rng.r#gen());
+            for (seat, controller) in self.controllers.iter().enumerate()
+            {
+                game.set_controller(seat, controller.clone());
+            }
+            let mut steps = 0;
+            while !game.is_game_over() && steps < MAX_STEPS_PER_GAME
+            {
+                game.step();
+                steps += 1;
+            }
+
+            let winner = game.winner();
+            results.push(GameResult { winner, starting_player, turns: game.turns });
+
+            if let Some(w) = winner
+            {
+                wins[w] += 1;
+                let loser = 1 - w;
+                starting_player = if strategies[loser].play_first(loser, &results) { loser } else { w };
+            }
+        }
+
+        let winner = (0..2).find(|&p| wins[p] >= self.games_to_win);
+        MatchResult { games: results, winner }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    struct CountingStrategy
+    {
+        calls: u32,
+    }
+
+    impl SideboardStrategy for CountingStrategy
+    {
+        fn sideboard(&mut self, _player: usize, deck: &Deck, _results: &[GameResult]) -> Deck
+        {
+            self.calls += 1;
+            deck.clone()
+        }
+    }
+
+    #[test]
+    fn best_of_three_with_loser_on_the_play()
+    {
+        let mut m = Match::new_seeded(Deck::example(), Deck::example(), 11);
+        let mut a = CountingStrategy { calls: 0 };
+        let mut b = NoSideboarding;
+        let result = m.run([&mut a, &mut b]);
+
+        assert!(result.games.len() >= 2 && result.games.len() <= 5);
+        assert_eq!(a.calls as usize, result.games.len() - 1, "sideboarding happens between games");
+
+        for pair in result.games.windows(2)
+        {
+            if let Some(w) = pair[0].winner
+            {
+                assert_eq!(pair[1].starting_player, 1 - w);
+            }
+        }
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use crate::ability::Target;
+use crate::action::Action;
+use crate::belief::Belief;
+use crate::card::Card;
+use crate::controller::{GreedyController, HeuristicController, PlayerController};
+use crate::game::{GameState, GameStep, Zone};
+
+/// Search budget and tuning for [`MctsController`].
+#[derive(Clone, Debug)]
+pub struct MctsConfig
+{
+    pub iterations: u32,
+    pub time_budget: Option<Duration>,   // stop early when this runs out
+    pub exploration: f64,                // UCB1 constant
+    pub rollout_turns: u32,              // turns to look ahead before scoring the position instead
+    pub prior_weight: f64,               // bonus for the rollout policy's own choice, fading with visits
+    pub determinizations: u32,           // worlds sampled per search
+}
+
+impl Default for MctsConfig
+{
+    fn default() -> Self
+    {
+        MctsConfig { iterations: 300, time_budget: None, exploration: 1.4, rollout_turns: 8, prior_weight: 0.5, determinizations: 20 }
+    }
+}
+
+#[derive(Default)]
+struct Node
+{
+    visits: u32,
+    reward: f64,
+    children: Vec<(Action, Node)>,
+}
+
+impl Node
+{
+    fn child(&mut self, action: &Action) -> Option<&mut Node>
+    {
+        self.children.iter_mut().find(|(a, _)| a == action).map(|(_, n)| n)
+    }
+}
+
+/// Copy of `game` as `viewer` might imagine it: the opponents' hands and
+/// every library are redrawn from the cards `viewer` can't see, with hands
+/// dealt according to `viewer`'s [`Belief`] about each opponent.
+pub fn determinize(game: &GameState, viewer: usize, rng: &mut StdRng) -> GameState
+{
+    let beliefs: Vec<Option<Belief>> = (0..game.players.len())
+        .map(|p| (p != viewer).then(|| game.belief(viewer, p)))
+        .collect();
+    let mut world = game.clone();
+    world.events.clear();
+
+    for (p, player) in world.players.iter_mut().enumerate()
+    {
+        let zones = &mut player.zones;
+        if p == viewer
+        {
+            zones.get_mut(&Zone::Library).unwrap().shuffle(rng);
+            continue;
+        }
+
+        let mut unseen = std::mem::take(zones.get_mut(&Zone::Hand).unwrap());
+        unseen.append(zones.get_mut(&Zone::Library).unwrap());
+        unseen.shuffle(rng);
+        let mut in_hand = beliefs[p].as_ref().unwrap().sample_hand(&unseen, rng);
+        in_hand.sort_unstable_by(|a, b| b.cmp(a));
+        let hand: Vec<Card> = in_hand.into_iter().map(|i| unseen.remove(i)).collect();
+        zones.insert(Zone::Hand, hand);
+        zones.insert(Zone::Library, unseen);
+    }
+    world.rng = StdRng::seed_from_u64(rng.r#gen());
+    world
+}
+
+/// Searches the player's own decisions with MCTS over determinized worlds:
+/// every iteration takes one of the sampled worlds, walks the tree with
+/// UCB1, and plays the rest out with the heuristic controller.
+#[derive(Clone)]
+pub struct MctsController
+{
+    pub config: MctsConfig,
+    rng: StdRng,
+}
+
+impl MctsController
+{
+    pub fn new(config: MctsConfig) -> Self
+    {
+        MctsController { config, rng: StdRng::from_entropy() }
+    }
+
+    pub fn seeded(config: MctsConfig, seed: u64) -> Self
+    {
+        MctsController { config, rng: StdRng::seed_from_u64(seed) }
+    }
+
+    /// Best of the currently legal actions for `player`.
+    pub fn search(&mut self, game: &GameState, player: usize) -> Option<Action>
+    {
+        let actions = distinct_actions(game, player);
+        if actions.len() <= 1
+        {
+            return actions.into_iter().next();
+        }
+
+        // A fixed set of worlds, so every move at the root is tried against
+        // the same hidden cards and the comparison isn't drowned in luck
+        let worlds: Vec<GameState> = (0..self.config.determinizations.max(1))
+            .map(|_|
+            {
+                let mut world = determinize(game, player, &mut self.rng);
+                for seat in 0..world.players.len()
+                {
+                    world.set_controller(seat, Box::new(HeuristicController));
+                }
+                world
+            })
+            .collect();
+        let preferred = policy_action(game, player);
+
+        let started = Instant::now();
+        let mut root = Node::default();
+        for _ in 0..self.config.iterations
+        {
+            if self.config.time_budget.is_some_and(|budget| started.elapsed() >= budget)
+            {
+                break;
+            }
+
+            let action = self.choose(&root, &actions, preferred.as_ref());
+            let tried = root.child(&action).map_or(0, |n| n.visits as usize);
+            let mut world = worlds[tried % worlds.len()].clone();
+            self.descend(&mut root, action, &mut world, player, game.turns);
+        }
+
+        // Most reliably good, so a lucky streak on a rarely tried move doesn't win
+        let lower_bound = |n: &Node| n.reward / n.visits as f64 - 1.0 / (n.visits as f64).sqrt();
+        root.children.iter()
+            .max_by(|(_, a), (_, b)| lower_bound(a).total_cmp(&lower_bound(b)))
+            .map(|(a, _)| a.clone())
+            .or_else(|| actions.into_iter().next())
+    }
+
+    /// Next move to try at `node`: untried moves first (the policy's choice
+    /// before the rest), then UCB1 with a fading bonus for the policy's choice.
+    fn choose(&mut self, node: &Node, legal: &[Action], preferred: Option<&Action>) -> Action
+    {
+        let untried: Vec<&Action> = legal.iter().filter(|a| !node.children.iter().any(|(c, _)| c == *a)).collect();
+        if let Some(&action) = untried.iter().find(|a| Some(**a) == preferred).or_else(|| untried.choose(&mut self.rng))
+        {
+            return action.clone();
+        }
+
+        let parent_visits = node.visits.max(1) as f64;
+        let ucb = |a: &Action|
+        {
+            let n = node.children.iter().find(|(x, _)| x == a).map(|(_, n)| n).unwrap();
+            let bias = if Some(a) == preferred { self.config.prior_weight / n.visits as f64 } else { 0.0 };
+            n.reward / n.visits as f64 + self.config.exploration * (parent_visits.ln() / n.visits as f64).sqrt() + bias
+        };
+        legal.iter().max_by(|a, b| ucb(a).total_cmp(&ucb(b))).cloned().unwrap()
+    }
+
+    /// Play `action` in `world` and continue below `node`: roll out from a
+    /// new child, or keep walking the tree through an existing one. An action
+    /// this world doesn't allow is neither expanded nor credited.
+    fn descend(&mut self, node: &mut Node, action: Action, world: &mut GameState, player: usize, start_turn: u32) -> f64
+    {
+        // The player's own cards are the same in every world, so this shouldn't happen
+        let applied = world.apply_action(player, action.clone());
+        debug_assert!(applied.is_ok(), "{:?} is illegal in a determinized world: {:?}", action, applied);
+        if applied.is_err()
+        {
+            return evaluate(world, player, start_turn);
+        }
+
+        let reward = match node.child(&action)
+        {
+            Some(child) => self.iterate(child, world, player, start_turn),
+            None =>
+            {
+                let reward = rollout(world, player, start_turn + self.config.rollout_turns, start_turn);
+                node.children.push((action, Node { visits: 1, reward, children: Vec::new() }));
+                reward
+            }
+        };
+
+        node.visits += 1;
+        node.reward += reward;
+        reward
+    }
+
+    fn iterate(&mut self, node: &mut Node, world: &mut GameState, player: usize, start_turn: u32) -> f64
+    {
+        if !advance_to_decision(world, player, start_turn + self.config.rollout_turns)
+        {
+            let reward = evaluate(world, player, start_turn);
+            node.visits += 1;
+            node.reward += reward;
+            return reward;
+        }
+
+        let legal = distinct_actions(world, player);
+        let preferred = policy_action(world, player);
+        let action = self.choose(node, &legal, preferred.as_ref());
+        self.descend(node, action, world, player, start_turn)
+    }
+}
+
+/// What the heuristic rollout policy would do in `world`, as an action.
+fn policy_action(world: &GameState, player: usize) -> Option<Action>
+{
+    let mut policy = HeuristicController;
+    match world.step
+    {
+        GameStep::Main => Some(policy.main_action(world, player).unwrap_or(Action::PassPriority)),
+        GameStep::DeclareAttackers => Some(Action::Attack(policy.declare_attackers(world, player))),
+        GameStep::DeclareBlockers =>
+        {
+            let mut blocks: Vec<(usize, usize)> = policy.declare_blockers(world, player, &world.attacking_creatures).into_iter().collect();
+            blocks.sort_unstable();
+            Some(Action::Block(blocks))
+        }
+        _ => None,
+    }
+}
+
+/// Legal actions with copies of the same card in the same zone counted once.
+fn distinct_actions(world: &GameState, player: usize) -> Vec<Action>
+{
+    let zones = &world.players[player].zones;
+    let mut seen = Vec::new();
+    world.legal_actions(player).into_iter()
+        .filter(|action|
+        {
+            let source = match action
+            {
+                Action::PlayLand { zone, index } | Action::Cast { zone, index, .. } => (*zone, &zones[zone][*index].name),
+                _ => return true,
+            };
+            let new = !seen.contains(&source);
+            seen.push(source);
+            new
+        })
+        .collect()
+}
+
+/// Run the game forward until `player` has more than one option. Returns false
+/// if the game ended or ran past the turn limit first.
+fn advance_to_decision(world: &mut GameState, player: usize, turn_limit: u32) -> bool
+{
+    loop
+    {
+        if world.is_game_over() || world.turns > turn_limit
+        {
+            return false;
+        }
+        let decision_step = matches!(world.step, GameStep::Main | GameStep::DeclareAttackers | GameStep::DeclareBlockers);
+        if decision_step && distinct_actions(world, player).len() > 1
+        {
+            return true;
+        }
+        world.step();
+    }
+}
+
+fn rollout(world: &mut GameState, player: usize, turn_limit: u32, start_turn: u32) -> f64
+{
+    while !world.is_game_over() && world.turns <= turn_limit
+    {
+        world.step();
+    }
+    evaluate(world, player, start_turn)
+}
+
+/// Near 1 for a win and near 0 for a loss, with quicker wins and slower
+/// losses scoring better; unfinished games score by life and board.
+fn evaluate(world: &GameState, player: usize, start_turn: u32) -> f64
+{
+    if world.is_game_over()
+    {
+        let delay = 0.01 * (world.turns - start_turn).min(40) as f64;
+        return match world.winner()
+        {
+            Some(w) if w == player => 1.0 - delay,
+            Some(_) => delay,
+            None => 0.5,
+        };
+    }
+
+    let score = |p: usize|
+    {
+        let power: i32 = world.players[p].zones[&Zone::Battlefield].iter()
+            .filter_map(crate::creature::creature_stats)
+            .map(|s| s.power as i32)
+            .sum();
+        world.players[p].life + 2 * power
+    };
+    let opponent = (player + 1) % world.players.len();
+    let margin = (score(player) - score(opponent)) as f64;
+    0.5 + 0.5 * (margin / 20.0).tanh()
+}
+
+impl PlayerController for MctsController
+{
+    fn name(&self) -> &str
+    {
+        "mcts"
+    }
+
+    fn keep_hand(&mut self, game: &GameState, player: usize, mulligans: u32) -> bool
+    {
+        HeuristicController.keep_hand(game, player, mulligans)
+    }
+
+    fn cards_to_bottom(&mut self, game: &GameState, player: usize, count: usize) -> Vec<usize>
+    {
+        HeuristicController.cards_to_bottom(game, player, count)
+    }
+
+    fn main_action(&mut self, game: &GameState, player: usize) -> Option<Action>
+    {
+        match self.search(game, player)
+        {
+            Some(Action::PassPriority) | None => None,
+            other => other,
+        }
+    }
+
+    fn declare_attackers(&mut self, game: &GameState, player: usize) -> Vec<usize>
+    {
+        match self.search(game, player)
+        {
+            Some(Action::Attack(attackers)) => attackers,
+            _ => HeuristicController.declare_attackers(game, player),
+        }
+    }
+
+    fn declare_blockers(&mut self, game: &GameState, player: usize, attackers: &[usize]) -> HashMap<usize, usize>
+    {
+        match self.search(game, player)
+        {
+            Some(Action::Block(blocks)) => blocks.into_iter().collect(),
+            _ => HeuristicController.declare_blockers(game, player, attackers),
+        }
+    }
+
+    fn choose_player_target(&mut self, game: &GameState, player: usize, target: Target) -> usize
+    {
+        GreedyController.choose_player_target(game, player, target)
+    }
+
+    fn choose_discard(&mut self, game: &GameState, player: usize) -> usize
+    {
+        HeuristicController.choose_discard(game, player)
+    }
+
+    fn choose_scry(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        HeuristicController.choose_scry(game, player, count)
+    }
+
+    fn choose_surveil(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        HeuristicController.choose_surveil(game, player, count)
+    }
+
+    fn box_clone(&self) -> Box<dyn PlayerController>
+    {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{grizzly_bears, Deck};
+
+    #[test]
+    fn determinizing_keeps_zone_sizes_and_own_hand()
+    {
+        let gs = GameState::new_seeded(2, &Deck::example(), 8);
+        let world = determinize(&gs, 0, &mut StdRng::seed_from_u64(1));
+
+        for p in 0..2
+        {
+            assert_eq!(world.players[p].zones[&Zone::Hand].len(), gs.players[p].zones[&Zone::Hand].len());
+            assert_eq!(world.players[p].zones[&Zone::Library].len(), gs.players[p].zones[&Zone::Library].len());
+        }
+        let names = |g: &GameState| g.players[0].zones[&Zone::Hand].iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&world), names(&gs));
+    }
+
+    #[test]
+    fn finds_lethal_attack()
+    {
+        let mut gs = GameState::new_seeded(2, &Deck::example(), 8);
+        gs.step = GameStep::DeclareAttackers;
+        gs.players[0].zones.get_mut(&Zone::Battlefield).unwrap().extend([grizzly_bears(), grizzly_bears()]);
+        gs.players[1].life = 4;
+
+        let mut mcts = MctsController::seeded(MctsConfig { iterations: 200, ..MctsConfig::default() }, 3);
+        assert_eq!(mcts.declare_attackers(&gs, 0), vec![0, 1]);
+    }
+}
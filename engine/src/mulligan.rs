@@ -0,0 +1,447 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::ability::Target;
+use crate::action::Action;
+use crate::card::{Card, CardType};
+use crate::controller::{default_controller, PlayerController};
+use crate::game::{GameState, Zone, OPENING_HAND_SIZE};
+use crate::mcts::determinize;
+
+// Simulated games still running after this many turns count as draws
+const ROLLOUT_TURN_LIMIT: u32 = 50;
+
+/// Decides whether to keep an opening hand and what to bottom afterwards.
+pub trait MulliganStrategy: Send + Sync
+{
+    fn name(&self) -> &str;
+
+    /// Whether `player` keeps their current seven after `mulligans` mulligans.
+    fn keep(&mut self, game: &GameState, player: usize, mulligans: u32) -> bool;
+
+    /// Hand indices to put on the bottom after keeping.
+    fn bottom(&mut self, game: &GameState, player: usize, count: usize) -> Vec<usize>
+    {
+        balanced_bottom(&game.players[player].zones[&Zone::Hand], count)
+    }
+
+    fn box_clone(&self) -> Box<dyn MulliganStrategy>;
+}
+
+impl Clone for Box<dyn MulliganStrategy>
+{
+    fn clone(&self) -> Box<dyn MulliganStrategy>
+    {
+        self.box_clone()
+    }
+}
+
+impl std::fmt::Debug for Box<dyn MulliganStrategy>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Bottom extra lands while they outnumber spells, otherwise the most expensive spell.
+pub fn balanced_bottom(hand: &[Card], count: usize) -> Vec<usize>
+{
+    let mut left: Vec<usize> = (0..hand.len()).collect();
+    let mut chosen = Vec::new();
+    while chosen.len() < count && !left.is_empty()
+    {
+        let lands = left.iter().filter(|&&i| hand[i].is_type(CardType::Land)).count();
+        let pick = if lands * 2 > left.len()
+        {
+            left.iter().rposition(|&i| hand[i].is_type(CardType::Land))
+        }
+        else
+        {
+            left.iter().enumerate()
+                .filter(|&(_, &i)| !hand[i].is_type(CardType::Land))
+                .max_by_key(|&(_, &i)| hand[i].cost)
+                .map(|(pos, _)| pos)
+        };
+        chosen.push(left.remove(pick.unwrap_or(left.len() - 1)));
+    }
+    chosen
+}
+
+/// The cards `player` would keep after bottoming `mulligans` of them.
+fn kept_cards(game: &GameState, player: usize, mulligans: u32) -> Vec<&Card>
+{
+    let hand = &game.players[player].zones[&Zone::Hand];
+    let bottom = balanced_bottom(hand, mulligans as usize);
+    hand.iter().enumerate().filter(|(i, _)| !bottom.contains(i)).map(|(_, c)| c).collect()
+}
+
+/// Keep when the land count after bottoming is within `min..=max`, and
+/// always once the hand is down to `keep_at` cards.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LandRange
+{
+    pub min: usize,
+    pub max: usize,
+    pub keep_at: usize,
+}
+
+impl Default for LandRange
+{
+    fn default() -> Self
+    {
+        LandRange { min: 2, max: 4, keep_at: 5 }
+    }
+}
+
+impl LandRange
+{
+    fn accepts(&self, game: &GameState, player: usize, mulligans: u32) -> bool
+    {
+        let kept = kept_cards(game, player, mulligans);
+        let lands = kept.iter().filter(|c| c.is_type(CardType::Land)).count();
+        kept.len() <= self.keep_at || (self.min..=self.max).contains(&lands)
+    }
+}
+
+impl MulliganStrategy for LandRange
+{
+    fn name(&self) -> &str
+    {
+        "land range"
+    }
+
+    fn keep(&mut self, game: &GameState, player: usize, mulligans: u32) -> bool
+    {
+        self.accepts(game, player, mulligans)
+    }
+
+    fn box_clone(&self) -> Box<dyn MulliganStrategy>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// A [`LandRange`] hand that also holds at least `count` creatures costing
+/// `max_cost` or less, so it has something to do in the first turns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurveRequirement
+{
+    pub lands: LandRange,
+    pub max_cost: u32,
+    pub count: usize,
+}
+
+impl Default for CurveRequirement
+{
+    fn default() -> Self
+    {
+        CurveRequirement { lands: LandRange::default(), max_cost: 3, count: 1 }
+    }
+}
+
+impl MulliganStrategy for CurveRequirement
+{
+    fn name(&self) -> &str
+    {
+        "curve requirement"
+    }
+
+    fn keep(&mut self, game: &GameState, player: usize, mulligans: u32) -> bool
+    {
+        let kept = kept_cards(game, player, mulligans);
+        if kept.len() <= self.lands.keep_at
+        {
+            return true;
+        }
+
+        let early = kept.iter().filter(|c| c.is_type(CardType::Creature) && c.cost <= self.max_cost).count();
+        self.lands.accepts(game, player, mulligans) && early >= self.count
+    }
+
+    fn box_clone(&self) -> Box<dyn MulliganStrategy>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// Plays `samples` games out from the current hand and from a fresh hand one
+/// card smaller, with unseen cards determinized, and keeps whichever wins more.
+#[derive(Clone, Debug)]
+pub struct SimulatedKeep
+{
+    pub samples: u32,
+    rng: StdRng,
+}
+
+impl SimulatedKeep
+{
+    pub fn new(samples: u32, seed: u64) -> Self
+    {
+        SimulatedKeep { samples, rng: StdRng::seed_from_u64(seed) }
+    }
+
+    fn win_rate(&mut self, game: &GameState, player: usize, mulligans: u32, redeal: bool) -> f64
+    {
+        let mut score = 0.0;
+        for _ in 0..self.samples
+        {
+            let mut world = determinize(game, player, &mut self.rng);
+            for seat in 0..world.players.len()
+            {
+                world.set_controller(seat, default_controller());
+            }
+            world.mulligan_strategies.clear();
+            if redeal
+            {
+                world.redeal_hand(player);
+            }
+            let chosen = balanced_bottom(&world.players[player].zones[&Zone::Hand], mulligans as usize);
+            world.bottom_from_hand(player, chosen, mulligans as usize);
+
+            while !world.is_game_over() && world.turns < ROLLOUT_TURN_LIMIT
+            {
+                world.step();
+            }
+            score += match world.winner()
+            {
+                Some(w) if w == player => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+        }
+        score / self.samples.max(1) as f64
+    }
+}
+
+impl MulliganStrategy for SimulatedKeep
+{
+    fn name(&self) -> &str
+    {
+        "simulated"
+    }
+
+    fn keep(&mut self, game: &GameState, player: usize, mulligans: u32) -> bool
+    {
+        if mulligans as usize + 1 >= OPENING_HAND_SIZE
+        {
+            return true;
+        }
+        self.win_rate(game, player, mulligans, false) >= self.win_rate(game, player, mulligans + 1, true)
+    }
+
+    fn box_clone(&self) -> Box<dyn MulliganStrategy>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// Lets `strategy` take the mulligan decisions of any controller.
+#[derive(Clone)]
+pub struct MulliganController
+{
+    pub strategy: Box<dyn MulliganStrategy>,
+    pub inner: Box<dyn PlayerController>,
+}
+
+impl MulliganController
+{
+    pub fn new(strategy: Box<dyn MulliganStrategy>, inner: Box<dyn PlayerController>) -> Self
+    {
+        MulliganController { strategy, inner }
+    }
+}
+
+impl PlayerController for MulliganController
+{
+    fn name(&self) -> &str
+    {
+        self.inner.name()
+    }
+
+    fn keep_hand(&mut self, game: &GameState, player: usize, mulligans: u32) -> bool
+    {
+        self.strategy.keep(game, player, mulligans)
+    }
+
+    fn cards_to_bottom(&mut self, game: &GameState, player: usize, count: usize) -> Vec<usize>
+    {
+        self.strategy.bottom(game, player, count)
+    }
+
+    fn main_action(&mut self, game: &GameState, player: usize) -> Option<Action>
+    {
+        self.inner.main_action(game, player)
+    }
+
+    fn declare_attackers(&mut self, game: &GameState, player: usize) -> Vec<usize>
+    {
+        self.inner.declare_attackers(game, player)
+    }
+
+    fn declare_blockers(&mut self, game: &GameState, player: usize, attackers: &[usize]) -> HashMap<usize, usize>
+    {
+        self.inner.declare_blockers(game, player, attackers)
+    }
+
+    fn choose_player_target(&mut self, game: &GameState, player: usize, target: Target) -> usize
+    {
+        self.inner.choose_player_target(game, player, target)
+    }
+
+    fn choose_discard(&mut self, game: &GameState, player: usize) -> usize
+    {
+        self.inner.choose_discard(game, player)
+    }
+
+    fn choose_scry(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        self.inner.choose_scry(game, player, count)
+    }
+
+    fn choose_surveil(&mut self, game: &GameState, player: usize, count: usize) -> Vec<bool>
+    {
+        self.inner.choose_surveil(game, player, count)
+    }
+
+    fn box_clone(&self) -> Box<dyn PlayerController>
+    {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HandSizeStats
+{
+    pub offered: u32,   // hands of this size looked at
+    pub kept: u32,
+    pub wins: u32,      // games won after keeping this size
+}
+
+impl HandSizeStats
+{
+    pub fn keep_rate(&self) -> f64
+    {
+        self.kept as f64 / self.offered.max(1) as f64
+    }
+
+    pub fn win_rate(&self) -> f64
+    {
+        self.wins as f64 / self.kept.max(1) as f64
+    }
+}
+
+/// Aggregate results of one strategy over many games, by kept hand size.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MulliganReport
+{
+    pub strategy: String,
+    pub games: u32,
+    pub wins: u32,
+    pub by_hand_size: BTreeMap<usize, HandSizeStats>,
+}
+
+impl MulliganReport
+{
+    /// Record a game where the player mulliganed `mulligans` times.
+    pub fn record(&mut self, mulligans: u32, won: bool)
+    {
+        self.games += 1;
+        self.wins += won as u32;
+        for m in 0..=mulligans
+        {
+            let stats = self.by_hand_size.entry(OPENING_HAND_SIZE.saturating_sub(m as usize)).or_default();
+            stats.offered += 1;
+            if m == mulligans
+            {
+                stats.kept += 1;
+                stats.wins += won as u32;
+            }
+        }
+    }
+
+    pub fn win_rate(&self) -> f64
+    {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears, Deck};
+    use crate::controller::{Decision, ScriptedController};
+    use crate::event::GameEvent;
+
+    // Opening hand of `lands` Forests and `7 - lands` Bears over a 50/50 library
+    fn game_with_hand(lands: usize) -> GameState
+    {
+        let mut cards: Vec<Card> = (0..7).map(|i| if i < lands { forest() } else { grizzly_bears() }).collect();
+        cards.extend((0..33).map(|i| if i % 2 == 0 { forest() } else { grizzly_bears() }));
+        let deck = Deck { cards, sideboard: Vec::new() };
+        GameState::new_stacked(&[&deck, &deck], 0)
+    }
+
+    #[test]
+    fn land_range_and_curve_rules()
+    {
+        let mut range = LandRange::default();
+        assert!(range.keep(&game_with_hand(3), 0, 0));
+        assert!(!range.keep(&game_with_hand(0), 0, 0));
+        assert!(!range.keep(&game_with_hand(7), 0, 0));
+        // Five lands are too many, unless one goes to the bottom
+        assert!(!range.keep(&game_with_hand(5), 0, 0));
+        assert!(range.keep(&game_with_hand(5), 0, 1));
+
+        let mut curve = CurveRequirement { max_cost: 1, ..CurveRequirement::default() };
+        assert!(!curve.keep(&game_with_hand(3), 0, 0), "Bears cost 2");
+        curve.max_cost = 2;
+        assert!(curve.keep(&game_with_hand(3), 0, 0));
+    }
+
+    #[test]
+    fn balanced_bottom_trims_the_excess()
+    {
+        let hand = vec![forest(), forest(), forest(), forest(), forest(), grizzly_bears(), grizzly_bears()];
+        let bottom = balanced_bottom(&hand, 2);
+        assert_eq!(bottom.len(), 2);
+        assert!(bottom.iter().all(|&i| hand[i].is_type(CardType::Land)));
+    }
+
+    #[test]
+    fn simulated_keep_mulligans_a_flooded_hand()
+    {
+        let mut sim = SimulatedKeep::new(30, 4);
+        assert!(!sim.keep(&game_with_hand(7), 0, 0));
+        assert!(sim.keep(&game_with_hand(2), 0, 0));
+    }
+
+    #[test]
+    fn seat_strategy_overrides_the_controller()
+    {
+        let mut game = game_with_hand(7);
+        game.set_controller(0, Box::new(ScriptedController::new([Decision::Keep(true)])));
+        game.set_mulligan_strategy(0, Box::new(LandRange::default()));
+        game.resolve_mulligans();
+
+        // Seven Forests are a mulligan for the strategy; the script was never asked
+        assert!(game.events.iter().any(|e| matches!(e, GameEvent::Mulliganed { player: 0, .. })));
+        assert_eq!(game.controllers[0].remaining(), 1);
+    }
+
+    #[test]
+    fn report_counts_offers_by_size()
+    {
+        let mut report = MulliganReport::default();
+        report.record(0, true);
+        report.record(2, false);
+
+        assert_eq!(report.by_hand_size[&7], HandSizeStats { offered: 2, kept: 1, wins: 1 });
+        assert_eq!(report.by_hand_size[&6].kept, 0);
+        assert_eq!(report.by_hand_size[&5], HandSizeStats { offered: 1, kept: 1, wins: 0 });
+        assert_eq!(report.win_rate(), 0.5);
+    }
+}
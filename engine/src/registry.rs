@@ -0,0 +1,81 @@
+use std::any::TypeId;
+use std::sync::{LazyLock, RwLock};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::card::{CardFragmentKind, Fragment};
+
+// Lets crates outside the engine add fragment kinds that survive serialization.
+
+struct Registration
+{
+    tag: &'static str,
+    type_id: TypeId,
+    to_value: fn(&dyn Fragment) -> Option<serde_json::Value>,
+    from_value: fn(serde_json::Value) -> Result<Box<dyn Fragment>, String>,
+}
+
+static REGISTRY: LazyLock<RwLock<Vec<Registration>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+fn to_value<T: Fragment + Serialize>(fragment: &dyn Fragment) -> Option<serde_json::Value>
+{
+    fragment.as_any().downcast_ref::<T>().and_then(|f| serde_json::to_value(f).ok())
+}
+
+fn from_value<T: Fragment + DeserializeOwned>(value: serde_json::Value) -> Result<Box<dyn Fragment>, String>
+{
+    serde_json::from_value::<T>(value)
+        .map(|f| Box::new(f) as Box<dyn Fragment>)
+        .map_err(|e| e.to_string())
+}
+
+/// Register fragment type `T` under `tag`. Cards can then store it under
+/// `CardFragmentKind::Custom(tag)` and be serialized without losing it.
+/// Registering the same type and tag again is a no-op.
+pub fn register_fragment<T>(tag: &'static str) -> Result<(), String>
+where
+    T: Fragment + Serialize + DeserializeOwned,
+{
+    if CardFragmentKind::BUILT_IN.iter().any(|k| k.tag() == tag)
+    {
+        return Err(format!("\"{}\" is a built-in fragment kind", tag));
+    }
+
+    let type_id = TypeId::of::<T>();
+    let mut registry = REGISTRY.write().unwrap();
+    if let Some(existing) = registry.iter().find(|r| r.tag == tag || r.type_id == type_id)
+    {
+        return if existing.tag == tag && existing.type_id == type_id
+        {
+            Ok(())
+        }
+        else
+        {
+            Err(format!("fragment tag \"{}\" or its type is already registered as \"{}\"", tag, existing.tag))
+        };
+    }
+
+    registry.push(Registration { tag, type_id, to_value: to_value::<T>, from_value: from_value::<T> });
+    Ok(())
+}
+
+pub(crate) fn registered_tag(tag: &str) -> Option<&'static str>
+{
+    REGISTRY.read().unwrap().iter().find(|r| r.tag == tag).map(|r| r.tag)
+}
+
+pub(crate) fn serialize_custom(fragment: &dyn Fragment) -> Option<(&'static str, serde_json::Value)>
+{
+    let type_id = fragment.as_any().type_id();
+    let registry = REGISTRY.read().unwrap();
+    let registration = registry.iter().find(|r| r.type_id == type_id)?;
+    (registration.to_value)(fragment).map(|value| (registration.tag, value))
+}
+
+pub(crate) fn deserialize_custom(tag: &str, value: serde_json::Value) -> Result<Box<dyn Fragment>, String>
+{
+    let registry = REGISTRY.read().unwrap();
+    let registration = registry.iter().find(|r| r.tag == tag)
+        .ok_or_else(|| format!("unregistered fragment kind \"{}\"", tag))?;
+    (registration.from_value)(value)
+}
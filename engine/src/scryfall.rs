@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::card::{CardType, Rarity};
+use crate::database::{CardDatabase, CardDatabaseError, CardDefinition};
+
+// Only the fields we map; everything else in the bulk file is ignored.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScryfallCard
+{
+    pub name: String,
+    #[serde(default)]
+    pub layout: String,
+    #[serde(default)]
+    pub type_line: String,
+    #[serde(default)]
+    pub cmc: f64,
+    #[serde(default)]
+    pub power: Option<String>,
+    #[serde(default)]
+    pub toughness: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub oracle_text: Option<String>,
+    #[serde(default)]
+    pub set: Option<String>,
+    #[serde(default)]
+    pub collector_number: Option<String>,
+    #[serde(default)]
+    pub rarity: Option<String>,
+    #[serde(default)]
+    pub artist: Option<String>,
+    #[serde(default)]
+    pub flavor_text: Option<String>,
+}
+
+/// How much of an imported card the engine can actually play.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SupportStatus
+{
+    Supported,
+    UnsupportedLayout(String),
+    UnsupportedType(String),
+    UnsupportedStats,
+    UnsupportedRulesText(Vec<String>),
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportedCard
+{
+    pub definition: CardDefinition,
+    pub status: SupportStatus,
+}
+
+/// Read a Scryfall bulk-data file (an array of card objects). Later
+/// printings of a name already seen are skipped.
+pub fn import_scryfall_bulk<P: AsRef<Path>>(path: P) -> Result<Vec<ImportedCard>, CardDatabaseError>
+{
+    let path = path.as_ref();
+    let file = File::open(path)
+        .map_err(|error| CardDatabaseError::Io { path: path.to_path_buf(), error })?;
+    let cards: Vec<ScryfallCard> = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| CardDatabaseError::Parse { path: path.to_path_buf(), message: e.to_string() })?;
+
+    let mut seen = HashSet::new();
+    Ok(cards.iter()
+        .filter(|c| seen.insert(c.name.clone()))
+        .map(convert)
+        .collect())
+}
+
+impl CardDatabase
+{
+    /// Add the fully supported cards from a Scryfall bulk file. Returns how many were added.
+    pub fn import_scryfall<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, CardDatabaseError>
+    {
+        let mut added = 0;
+        for imported in import_scryfall_bulk(path)?
+        {
+            if imported.status == SupportStatus::Supported
+            {
+                self.insert(imported.definition);
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+}
+
+pub fn convert(card: &ScryfallCard) -> ImportedCard
+{
+    let types = card_types(&card.type_line);
+    let power = card.power.as_deref().and_then(|p| p.parse::<u8>().ok());
+    let toughness = card.toughness.as_deref().and_then(|t| t.parse::<u8>().ok());
+    // Lines of implemented keywords are carried by `keywords`. Anything else is
+    // rules text the engine can't play; it's reported in the status and never
+    // stored as abilities, which would load as a vanilla body
+    let rules_text: Vec<String> = card.oracle_text.as_deref().unwrap_or("")
+        .lines()
+        .filter(|l| !l.is_empty() && !is_keyword_line(l, &card.keywords))
+        .map(|l| l.to_string())
+        .collect();
+
+    let status = if !card.layout.is_empty() && card.layout != "normal"
+    {
+        SupportStatus::UnsupportedLayout(card.layout.clone())
+    }
+    else if types.is_empty() || has_unmodelled_type(&card.type_line)
+    {
+        SupportStatus::UnsupportedType(card.type_line.clone())
+    }
+    else if types.contains(&CardType::Creature) && (power.is_none() || toughness.is_none())
+    {
+        // Variable stats such as "*" have no representation yet
+        SupportStatus::UnsupportedStats
+    }
+    else if rules_text.is_empty()
+    {
+        SupportStatus::Supported
+    }
+    else
+    {
+        SupportStatus::UnsupportedRulesText(rules_text)
+    };
+
+    let is_creature = types.contains(&CardType::Creature);
+    ImportedCard
+    {
+        definition: CardDefinition
+        {
+            name: card.name.clone(),
+            types,
+            cost: card.cmc as u32,
+            power: if is_creature { power } else { None },
+            toughness: if is_creature { toughness } else { None },
+            keywords: card.keywords.clone(),
+            abilities: Vec::new(),
+            set: card.set.as_ref().map(|s| s.to_ascii_uppercase()),
+            collector_number: card.collector_number.clone(),
+            rarity: card.rarity.as_deref().and_then(Rarity::from_name),
+            artist: card.artist.clone(),
+            flavor_text: card.flavor_text.clone(),
+        },
+        status,
+    }
+}
+
+// Types left of the em dash, e.g. "Artifact Creature — Golem".
+fn card_types(type_line: &str) -> Vec<CardType>
+{
+    let main = type_line.split('—').next().unwrap_or("");
+    let mut types = Vec::new();
+    for word in main.split_whitespace()
+    {
+        match word
+        {
+            "Land" => types.push(CardType::Land),
+            "Creature" => types.push(CardType::Creature),
+            _ => {}
+        }
+    }
+    types
+}
+
+fn has_unmodelled_type(type_line: &str) -> bool
+{
+    let main = type_line.split('—').next().unwrap_or("");
+    main.split_whitespace().any(|w| !matches!(w, "Land" | "Creature" | "Basic" | "Legendary" | "Snow"))
+}
+
+// Keywords the rules engine plays. Others import as unsupported rules text.
+const IMPLEMENTED_KEYWORDS: &[&str] = &["Vigilance"];
+
+// A line is supported if, once reminder text is removed, it only lists the
+// card's keywords and the engine implements all of them.
+fn is_keyword_line(line: &str, keywords: &[String]) -> bool
+{
+    let mut text = String::new();
+    let mut depth = 0;
+    for c in line.chars()
+    {
+        match c
+        {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 => text.push(c),
+            _ => {}
+        }
+    }
+
+    text.split(',')
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .all(|part| keywords.iter().any(|k| k.eq_ignore_ascii_case(part))
+            && IMPLEMENTED_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(part)))
+}
@@ -1,7 +1,18 @@
 use std::io::{self, Write};
 
 use crate::game::{GameState, ProgramState, StepCommand, GameStep};
-use crate::card::Deck;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::booster::{BoosterGenerator, Collation, Draft};
+use crate::card::{CardType, Deck};
+use crate::controller::{GreedyController, HeuristicController, RandomController};
+use crate::database::CardDatabase;
+use crate::match_play::{Match, SideboardStrategy};
+use crate::mcts::{MctsConfig, MctsController};
+use crate::event::GameEvent;
+use crate::mulligan::{MulliganReport, MulliganStrategy};
+use crate::tournament::{Entrant, Format, Leaderboard, Tournament};
 
 pub fn parse_command(input: &str) -> StepCommand
 {
@@ -12,6 +23,12 @@ pub fn parse_command(input: &str) -> StepCommand
         "g" => StepCommand::RunGame,
         "d" => StepCommand::RunDeck,
         "r" => StepCommand::RunAll,
+        "p" => StepCommand::Play,
+        "m" => StepCommand::Mulligans,
+        "b" => StepCommand::Draft,
+        "w" => StepCommand::Matches,
+        "v" => StepCommand::VsMcts,
+        "l" => StepCommand::Tournament,
         "q" => StepCommand::Quit,
         _   => StepCommand::Invalid,
     }
@@ -97,7 +114,7 @@ pub fn simulate_game(deck: &Deck, step_mode: StepCommand) -> (u32, StepCommand)
                 break;
             }
 
-            StepCommand::Invalid =>
+            StepCommand::Invalid | StepCommand::Play | StepCommand::Mulligans | StepCommand::Draft | StepCommand::Matches | StepCommand::VsMcts | StepCommand::Tournament =>
             {
                 mode = wait_for_command();
             }
@@ -107,7 +124,7 @@ pub fn simulate_game(deck: &Deck, step_mode: StepCommand) -> (u32, StepCommand)
     (game.turns, mode)
 }
 
-pub fn try_scenario(lands: u32, nonlands: u32, program_state: &mut ProgramState) -> f64
+pub fn scenario_deck(lands: u32, nonlands: u32) -> Deck
 {
     let mut cards = Vec::new();
 
@@ -121,20 +138,31 @@ pub fn try_scenario(lands: u32, nonlands: u32, program_state: &mut ProgramState)
         cards.push(crate::card::grizzly_bears());
     }
 
-    let deck = Deck { cards };
-    let games = 3000;
+    Deck { cards, sideboard: Vec::new() }
+}
+
+/// Average turns until the game ends over `games` games with `deck`.
+pub fn average_turns(deck: &Deck, games: u32, program_state: &mut ProgramState) -> f64
+{
     let mut total_turns = 0;
 
     for _ in 0..games
     {
-        let (turns, new_mode) = simulate_game(&deck, program_state.step_mode);
+        let (turns, new_mode) = simulate_game(deck, program_state.step_mode);
         total_turns += turns;
 
         // update ProgramState after simulate_game
         program_state.step_mode = new_mode;
     }
 
-    let avg_turns_to_death = total_turns as f64 / games as f64;
+    total_turns as f64 / games as f64
+}
+
+pub fn try_scenario(lands: u32, nonlands: u32, program_state: &mut ProgramState) -> f64
+{
+    let deck = scenario_deck(lands, nonlands);
+    let games = 3000;
+    let avg_turns_to_death = average_turns(&deck, games, program_state);
 
     if program_state.step_mode != StepCommand::Quit
     {
@@ -149,3 +177,205 @@ pub fn try_scenario(lands: u32, nonlands: u32, program_state: &mut ProgramState)
 
     avg_turns_to_death
 }
+
+/// Bot-draft a pod from `db` and simulate `games` games with each seat's
+/// deck. Returns the average turns per seat.
+pub fn try_draft(db: &CardDatabase, collation: Collation, seed: u64, games: u32, program_state: &mut ProgramState) -> Vec<f64>
+{
+    let generator = BoosterGenerator::new(db, collation);
+    let basic_land = db.create("Forest").unwrap_or_else(crate::card::forest);
+    let decks = Draft::default().bot_decks(&generator, &basic_land, &mut StdRng::seed_from_u64(seed));
+
+    let mut results = Vec::new();
+    for (seat, deck) in decks.iter().enumerate()
+    {
+        let avg = average_turns(deck, games, program_state);
+        if program_state.step_mode == StepCommand::Quit
+        {
+            break;
+        }
+        println!("Seat {}: {} lands, {} spells, average {:.4} turns over {} games", seat + 1, deck.count(CardType::Land), deck.cards.len() - deck.count(CardType::Land), avg, games);
+        results.push(avg);
+    }
+    results
+}
+
+/// Play `matches` best-of-three matches and return `deck_a`'s match win rate.
+/// Match `n` is seeded with `seed + n`, so the same seed gives the same result.
+pub fn match_win_rate(deck_a: &Deck, deck_b: &Deck, matches: u32, seed: u64, strategies: [&mut dyn SideboardStrategy; 2]) -> f64
+{
+    let [a, b] = strategies;
+    let mut wins = 0;
+    let mut draws = 0;
+
+    for match_number in 0..matches
+    {
+        let result = Match::new_seeded(deck_a.clone(), deck_b.clone(), seed + match_number as u64).run([&mut *a, &mut *b]);
+        match result.winner
+        {
+            Some(0) => wins += 1,
+            None => draws += 1,
+            _ => {}
+        }
+    }
+
+    let win_rate = wins as f64 / matches.max(1) as f64;
+    println!(
+        "Match win rate over {} matches: {:.2}% ({} drawn)",
+        matches,
+        win_rate * 100.0,
+        draws
+    );
+
+    win_rate
+}
+
+/// Play `games` games of `deck` (default controller) against `reference`
+/// piloted by MCTS, alternating who starts. Returns `deck`'s game win rate.
+pub fn benchmark_vs_mcts(deck: &Deck, reference: &Deck, games: u32, config: MctsConfig) -> f64
+{
+    let mut wins = 0;
+    let mut draws = 0;
+
+    for game_number in 0..games
+    {
+        let seed = game_number as u64;
+        let mut game = GameState::new_with_decks(&[deck, reference], game_number as usize % 2, seed);
+        game.set_controller(1, Box::new(MctsController::seeded(config.clone(), seed)));
+        while !game.is_game_over()
+        {
+            game.step();
+        }
+
+        match game.winner()
+        {
+            Some(0) => wins += 1,
+            None => draws += 1,
+            _ => {}
+        }
+    }
+
+    let win_rate = wins as f64 / games.max(1) as f64;
+    println!(
+        "Win rate against MCTS over {} games: {:.2}% ({} drawn)",
+        games,
+        win_rate * 100.0,
+        draws
+    );
+
+    win_rate
+}
+
+/// Round-robin of the built-in controllers, each piloting `deck`, with the
+/// tournament and the random controller seeded from `seed`.
+pub fn controller_tournament(deck: &Deck, seed: u64) -> Leaderboard
+{
+    let entrants = vec![
+        Entrant::new("greedy", deck.clone(), Box::new(GreedyController)),
+        Entrant::new("heuristic", deck.clone(), Box::new(HeuristicController)),
+        Entrant::new("random", deck.clone(), Box::new(RandomController::new(seed))),
+    ];
+    let board = Tournament::new_seeded(entrants, Format::RoundRobin, seed).run();
+    println!("Controller round-robin over {} rounds:", board.rounds);
+    print!("{}", board);
+    board
+}
+
+/// Play `games` games of `deck` with `strategy` taking its mulligans (and the
+/// default controller everything else) against `opponent`, alternating who
+/// starts. Reports keep and win rates by the hand size kept.
+pub fn evaluate_mulligans(deck: &Deck, opponent: &Deck, strategy: &dyn MulliganStrategy, games: u32) -> MulliganReport
+{
+    let mut report = MulliganReport { strategy: strategy.name().to_string(), ..MulliganReport::default() };
+
+    for game_number in 0..games
+    {
+        let seed = game_number as u64;
+        let mut game = GameState::new_with_decks(&[deck, opponent], game_number as usize % 2, seed);
+        game.set_mulligan_strategy(0, strategy.box_clone());
+        while !game.is_game_over()
+        {
+            game.step();
+        }
+
+        let mulligans = game.events.iter().filter(|e| matches!(e, GameEvent::Mulliganed { player: 0, .. })).count();
+        report.record(mulligans as u32, game.winner() == Some(0));
+    }
+
+    println!("Mulligan strategy '{}' over {} games: {:.2}% won", report.strategy, games, report.win_rate() * 100.0);
+    for (size, stats) in report.by_hand_size.iter().rev()
+    {
+        println!(
+            "  {} cards: kept {} of {} ({:.2}%), won {:.2}% of those",
+            size,
+            stats.kept,
+            stats.offered,
+            stats.keep_rate() * 100.0,
+            stats.win_rate() * 100.0
+        );
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::match_play::NoSideboarding;
+    use crate::mulligan::LandRange;
+
+    #[test]
+    fn seeded_match_win_rate_repeats()
+    {
+        let run = || match_win_rate(&scenario_deck(17, 23), &Deck::example(), 4, 7, [&mut NoSideboarding, &mut NoSideboarding]);
+        let rate = run();
+        assert!((0.0..=1.0).contains(&rate));
+        assert_eq!(rate, run());
+    }
+
+    #[test]
+    fn seeded_mcts_benchmark_repeats()
+    {
+        let config = MctsConfig { iterations: 20, determinizations: 2, ..MctsConfig::default() };
+        let run = || benchmark_vs_mcts(&scenario_deck(17, 23), &Deck::example(), 2, config.clone());
+        let rate = run();
+        assert!((0.0..=1.0).contains(&rate));
+        assert_eq!(rate, run());
+    }
+
+    #[test]
+    fn seeded_controller_tournament_repeats()
+    {
+        let board = controller_tournament(&Deck::example(), 3);
+        assert_eq!(board.rounds, 3);
+        assert_eq!(board.standings.len(), 3);
+        assert_eq!(board.standings, controller_tournament(&Deck::example(), 3).standings);
+    }
+
+    #[test]
+    fn draft_reports_every_seat()
+    {
+        let mut db = CardDatabase::new();
+        db.insert(crate::database::CardDefinition { name: String::from("Draft Bear"), types: vec![CardType::Creature], cost: 2, power: Some(2), toughness: Some(2), rarity: Some(crate::card::Rarity::Common), ..Default::default() });
+        let mut program_state = ProgramState::new();
+        program_state.step_mode = StepCommand::RunAll;
+
+        let results = try_draft(&db, Collation::standard(None), 3, 2, &mut program_state);
+        assert_eq!(results.len(), Draft::default().seats);
+        assert!(results.iter().all(|&turns| turns > 0.0));
+    }
+
+    #[test]
+    fn mulligan_report_covers_every_game()
+    {
+        let report = evaluate_mulligans(&Deck::example(), &Deck::example(), &LandRange::default(), 4);
+
+        assert_eq!(report.strategy, "land range");
+        assert_eq!(report.games, 4);
+        assert!(report.wins <= report.games);
+        assert_eq!(report.by_hand_size[&7].offered, 4);
+        assert_eq!(report.by_hand_size.values().map(|s| s.kept).sum::<u32>(), 4);
+        assert!(report.by_hand_size.values().all(|s| s.kept <= s.offered && s.wins <= s.kept));
+    }
+}
@@ -0,0 +1,388 @@
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::card::Deck;
+use crate::controller::PlayerController;
+use crate::match_play::{Match, NoSideboarding};
+
+// Glicko-1 constants
+const INITIAL_RATING: f64 = 1500.0;
+const INITIAL_DEVIATION: f64 = 350.0;
+const Q: f64 = std::f64::consts::LN_10 / 400.0;
+
+const POINTS_FOR_WIN: u32 = 3;
+const POINTS_FOR_DRAW: u32 = 1;
+
+/// A controller piloting a deck, under a name for the leaderboard.
+#[derive(Clone)]
+pub struct Entrant
+{
+    pub name: String,
+    pub deck: Deck,
+    pub controller: Box<dyn PlayerController>,
+}
+
+impl Entrant
+{
+    pub fn new(name: impl Into<String>, deck: Deck, controller: Box<dyn PlayerController>) -> Self
+    {
+        Entrant { name: name.into(), deck, controller }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format
+{
+    RoundRobin,
+    Swiss { rounds: u32 },
+}
+
+/// Glicko-1 rating with its deviation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rating
+{
+    pub rating: f64,
+    pub deviation: f64,
+}
+
+impl Default for Rating
+{
+    fn default() -> Self
+    {
+        Rating { rating: INITIAL_RATING, deviation: INITIAL_DEVIATION }
+    }
+}
+
+impl Rating
+{
+    /// 95% confidence interval for the true rating.
+    pub fn interval(&self) -> (f64, f64)
+    {
+        (self.rating - 1.96 * self.deviation, self.rating + 1.96 * self.deviation)
+    }
+
+    /// Expected score against `opponent`.
+    pub fn expected_score(&self, opponent: &Rating) -> f64
+    {
+        1.0 / (1.0 + 10f64.powf(-g(opponent.deviation) * (self.rating - opponent.rating) / 400.0))
+    }
+
+    /// Rating after one period of `results`, each an opponent's pre-period
+    /// rating and the score against them (1 win, 0.5 draw, 0 loss).
+    pub fn update(&self, results: &[(Rating, f64)]) -> Rating
+    {
+        if results.is_empty()
+        {
+            return *self;
+        }
+
+        let mut variance_inv = 0.0;
+        let mut improvement = 0.0;
+        for (opponent, score) in results
+        {
+            let g = g(opponent.deviation);
+            let expected = self.expected_score(opponent);
+            variance_inv += g * g * expected * (1.0 - expected);
+            improvement += g * (score - expected);
+        }
+
+        let d_squared_inv = Q * Q * variance_inv;
+        let precision = 1.0 / (self.deviation * self.deviation) + d_squared_inv;
+        Rating
+        {
+            rating: self.rating + Q / precision * improvement,
+            deviation: (1.0 / precision).sqrt(),
+        }
+    }
+}
+
+fn g(deviation: f64) -> f64
+{
+    let pi = std::f64::consts::PI;
+    1.0 / (1.0 + 3.0 * Q * Q * deviation * deviation / (pi * pi)).sqrt()
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Record
+{
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing
+{
+    pub name: String,
+    pub rating: Rating,
+    pub points: u32,
+    pub matches: Record,
+    pub games: Record,
+    pub byes: u32,
+    pub opponents: Vec<usize>,   // entrant indices played, in order
+}
+
+/// Final standings, best rating first.
+#[derive(Clone, Debug)]
+pub struct Leaderboard
+{
+    pub rounds: u32,
+    pub standings: Vec<Standing>,
+}
+
+impl fmt::Display for Leaderboard
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        writeln!(f, "{:>4}  {:<20} {:>7} {:>6}  {:>15}  {:>8}  {:>8}  {:>6}", "Rank", "Entrant", "Rating", "RD", "95% interval", "Matches", "Games", "Points")?;
+        for (rank, s) in self.standings.iter().enumerate()
+        {
+            let (low, high) = s.rating.interval();
+            writeln!(
+                f,
+                "{:>4}  {:<20} {:>7.0} {:>6.0}  {:>7.0}..{:<6.0}  {:>8}  {:>8}  {:>6}",
+                rank + 1,
+                s.name,
+                s.rating.rating,
+                s.rating.deviation,
+                low,
+                high,
+                format!("{}-{}-{}", s.matches.wins, s.matches.losses, s.matches.draws),
+                format!("{}-{}-{}", s.games.wins, s.games.losses, s.games.draws),
+                s.points
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays matches between entrants and rates them, one Glicko rating period per round.
+pub struct Tournament
+{
+    pub entrants: Vec<Entrant>,
+    pub format: Format,
+    pub games_to_win: u32,
+    rng: StdRng,
+}
+
+impl Tournament
+{
+    pub fn new(entrants: Vec<Entrant>, format: Format) -> Self
+    {
+        Self::with_rng(entrants, format, StdRng::from_entropy())
+    }
+
+    pub fn new_seeded(entrants: Vec<Entrant>, format: Format, seed: u64) -> Self
+    {
+        Self::with_rng(entrants, format, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(entrants: Vec<Entrant>, format: Format, rng: StdRng) -> Self
+    {
+        Tournament { entrants, format, games_to_win: 2, rng }
+    }
+
+    pub fn run(&mut self) -> Leaderboard
+    {
+        let mut standings: Vec<Standing> = self.entrants.iter().map(|e| Standing
+        {
+            name: e.name.clone(),
+            rating: Rating::default(),
+            points: 0,
+            matches: Record::default(),
+            games: Record::default(),
+            byes: 0,
+            opponents: Vec::new(),
+        }).collect();
+
+        let rounds = match self.format
+        {
+            Format::RoundRobin => round_robin(self.entrants.len()),
+            Format::Swiss { rounds } => (0..rounds).map(|_| Vec::new()).collect(),
+        };
+
+        for (round, fixed_pairings) in rounds.iter().enumerate()
+        {
+            let pairings = match self.format
+            {
+                Format::RoundRobin => fixed_pairings.clone(),
+                Format::Swiss { .. } => swiss_pairings(&standings),
+            };
+            vlog!(crate::ELoggingVerbosity::Verbose, "Round {}: {:?}", round + 1, pairings);
+            self.play_round(&pairings, &mut standings);
+        }
+
+        standings.sort_by(|a, b| b.rating.rating.total_cmp(&a.rating.rating).then(b.points.cmp(&a.points)));
+        Leaderboard { rounds: rounds.len() as u32, standings }
+    }
+
+    fn play_round(&mut self, pairings: &[(usize, Option<usize>)], standings: &mut [Standing])
+    {
+        let ratings: Vec<Rating> = standings.iter().map(|s| s.rating).collect();
+        let mut results: Vec<Vec<(Rating, f64)>> = vec![Vec::new(); standings.len()];
+
+        for &(a, b) in pairings
+        {
+            let Some(b) = b else
+            {
+                standings[a].byes += 1;
+                standings[a].points += POINTS_FOR_WIN;
+                continue;
+            };
+
+            let mut m = Match::new_seeded(self.entrants[a].deck.clone(), self.entrants[b].deck.clone(), self.rng.r#gen());
+            m.games_to_win = self.games_to_win;
+            m.controllers = [self.entrants[a].controller.clone(), self.entrants[b].controller.clone()];
+            let result = m.run([&mut NoSideboarding, &mut NoSideboarding]);
+
+            let seats = [a, b];
+            for game in &result.games
+            {
+                for (seat, &entrant) in seats.iter().enumerate()
+                {
+                    let opponent = seats[1 - seat];
+                    let score = match game.winner
+                    {
+                        None => { standings[entrant].games.draws += 1; 0.5 }
+                        Some(w) if w == seat => { standings[entrant].games.wins += 1; 1.0 }
+                        Some(_) => { standings[entrant].games.losses += 1; 0.0 }
+                    };
+                    results[entrant].push((ratings[opponent], score));
+                }
+            }
+
+            for (seat, &entrant) in seats.iter().enumerate()
+            {
+                let standing = &mut standings[entrant];
+                standing.opponents.push(seats[1 - seat]);
+                match result.winner
+                {
+                    None => { standing.matches.draws += 1; standing.points += POINTS_FOR_DRAW; }
+                    Some(w) if w == seat => { standing.matches.wins += 1; standing.points += POINTS_FOR_WIN; }
+                    Some(_) => standing.matches.losses += 1,
+                }
+            }
+        }
+
+        for (standing, results) in standings.iter_mut().zip(&results)
+        {
+            standing.rating = standing.rating.update(results);
+        }
+    }
+}
+
+/// All rounds of a round robin by the circle method. `None` is a bye.
+pub fn round_robin(entrants: usize) -> Vec<Vec<(usize, Option<usize>)>>
+{
+    let mut seats: Vec<Option<usize>> = (0..entrants).map(Some).collect();
+    if entrants % 2 == 1
+    {
+        seats.push(None);
+    }
+
+    let n = seats.len();
+    let mut rounds = Vec::new();
+    for _ in 1..n
+    {
+        let mut round = Vec::new();
+        for i in 0..n / 2
+        {
+            match (seats[i], seats[n - 1 - i])
+            {
+                (Some(a), b) | (b, Some(a)) => round.push((a, b)),
+                (None, None) => {}
+            }
+        }
+        rounds.push(round);
+        seats[1..].rotate_right(1);
+    }
+    rounds
+}
+
+/// Pairs entrants with similar points (then rating), avoiding rematches where
+/// possible. With an odd count the lowest-ranked entrant without a bye sits out.
+pub fn swiss_pairings(standings: &[Standing]) -> Vec<(usize, Option<usize>)>
+{
+    let mut order: Vec<usize> = (0..standings.len()).collect();
+    order.sort_by(|&a, &b| standings[b].points.cmp(&standings[a].points)
+        .then(standings[b].rating.rating.total_cmp(&standings[a].rating.rating))
+        .then(a.cmp(&b)));
+
+    let mut pairings = Vec::new();
+    if order.len() % 2 == 1
+    {
+        let fewest = order.iter().map(|&i| standings[i].byes).min().unwrap_or(0);
+        let pos = order.iter().rposition(|&i| standings[i].byes == fewest).unwrap_or(order.len() - 1);
+        pairings.push((order.remove(pos), None));
+    }
+
+    while !order.is_empty()
+    {
+        let a = order.remove(0);
+        let pos = order.iter().position(|b| !standings[a].opponents.contains(b)).unwrap_or(0);
+        pairings.push((a, Some(order.remove(pos))));
+    }
+    pairings
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::controller::{GreedyController, RandomController};
+
+    #[test]
+    fn glicko_matches_worked_example()
+    {
+        // Glickman's example: 1500/200 beats 1400/30, loses to 1550/100 and 1700/300
+        let player = Rating { rating: 1500.0, deviation: 200.0 };
+        let updated = player.update(&[
+            (Rating { rating: 1400.0, deviation: 30.0 }, 1.0),
+            (Rating { rating: 1550.0, deviation: 100.0 }, 0.0),
+            (Rating { rating: 1700.0, deviation: 300.0 }, 0.0),
+        ]);
+
+        assert!((updated.rating - 1464.1).abs() < 0.5, "{:?}", updated);
+        assert!((updated.deviation - 151.4).abs() < 0.5, "{:?}", updated);
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_once()
+    {
+        let rounds = round_robin(5);
+        assert_eq!(rounds.len(), 5);
+
+        let mut seen = std::collections::HashSet::new();
+        for round in &rounds
+        {
+            assert_eq!(round.iter().filter(|(_, b)| b.is_none()).count(), 1);
+            for &(a, b) in round
+            {
+                if let Some(b) = b
+                {
+                    assert!(seen.insert((a.min(b), a.max(b))));
+                }
+            }
+        }
+        assert_eq!(seen.len(), 10);
+    }
+
+    #[test]
+    fn swiss_leaderboard_rates_every_entrant()
+    {
+        let entrants = vec![
+            Entrant::new("greedy", Deck::example(), Box::new(GreedyController)),
+            Entrant::new("random a", Deck::example(), Box::new(RandomController::new(1))),
+            Entrant::new("random b", Deck::example(), Box::new(RandomController::new(2))),
+        ];
+        let board = Tournament::new_seeded(entrants, Format::Swiss { rounds: 2 }, 5).run();
+
+        assert_eq!(board.rounds, 2);
+        assert_eq!(board.standings.len(), 3);
+        assert!(board.standings.windows(2).all(|w| w[0].rating.rating >= w[1].rating.rating));
+        assert!(board.standings.iter().all(|s| s.byes <= 1 && s.rating.deviation < INITIAL_DEVIATION));
+        assert!(board.to_string().contains("greedy"));
+    }
+}
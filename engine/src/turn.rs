@@ -0,0 +1,62 @@
+use std::collections::VecDeque;
+use serde::{Serialize, Deserialize};
+
+use crate::game::GameStep;
+
+/// A one-shot "skip your next <step>" effect for a player.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StepSkip
+{
+    pub player: usize,
+    pub step: GameStep,
+}
+
+/// Pending changes to the normal turn cycle. Effects push entries here and
+/// `GameState::step` consumes them as the game reaches the affected point.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TurnQueue
+{
+    pub extra_turns: VecDeque<usize>,   // player indices; front is taken next
+    pub extra_combats: u32,             // additional combat phases this turn
+    pub skips: Vec<StepSkip>,
+}
+
+impl TurnQueue
+{
+    /// The most recently granted extra turn is taken first.
+    pub fn add_extra_turn(&mut self, player: usize)
+    {
+        self.extra_turns.push_front(player);
+    }
+
+    pub fn add_extra_combat(&mut self)
+    {
+        self.extra_combats += 1;
+    }
+
+    pub fn skip_next(&mut self, player: usize, step: GameStep)
+    {
+        self.skips.push(StepSkip { player, step });
+    }
+
+    /// Consume a pending skip for `player`'s `step`, if any.
+    pub fn take_skip(&mut self, player: usize, step: GameStep) -> bool
+    {
+        match self.skips.iter().position(|s| s.player == player && s.step == step)
+        {
+            Some(pos) =>
+            {
+                self.skips.remove(pos);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whose turn comes after `current`'s, taking extra turns first.
+    pub fn next_player(&mut self, current: usize, player_count: usize) -> usize
+    {
+        self.extra_combats = 0;
+        self.extra_turns.pop_front().unwrap_or((current + 1) % player_count)
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::card::Deck;
+use crate::database::CardDatabaseError;
+
+const BASIC_LANDS: [&str; 11] = [
+    "Plains", "Island", "Swamp", "Mountain", "Forest", "Wastes",
+    "Snow-Covered Plains", "Snow-Covered Island", "Snow-Covered Swamp", "Snow-Covered Mountain", "Snow-Covered Forest",
+];
+
+/// Get the path to the ban/restricted list shipped with the project
+pub fn ban_list_path() -> PathBuf
+{
+    crate::music::find_web_dir().join("data/banlist.toml")
+}
+
+pub fn is_basic_land(name: &str) -> bool
+{
+    BASIC_LANDS.contains(&name)
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DeckViolation
+{
+    TooFewCards { count: usize, minimum: usize },
+    TooManyCards { count: usize, maximum: usize },
+    TooManyCopies { card: String, count: usize, limit: usize },
+    SideboardTooLarge { count: usize, maximum: usize },
+    Banned { card: String },
+    Restricted { card: String, count: usize },
+}
+
+impl fmt::Display for DeckViolation
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            DeckViolation::TooFewCards { count, minimum } => write!(f, "deck has {} cards, needs at least {}", count, minimum),
+            DeckViolation::TooManyCards { count, maximum } => write!(f, "deck has {} cards, at most {} allowed", count, maximum),
+            DeckViolation::TooManyCopies { card, count, limit } => write!(f, "{} copies of \"{}\", limit is {}", count, card, limit),
+            DeckViolation::SideboardTooLarge { count, maximum } => write!(f, "sideboard has {} cards, at most {} allowed", count, maximum),
+            DeckViolation::Banned { card } => write!(f, "\"{}\" is banned", card),
+            DeckViolation::Restricted { card, count } => write!(f, "\"{}\" is restricted to one copy, deck has {}", card, count),
+        }
+    }
+}
+
+// On-disk ban list: `banned = [...]` and `restricted = [...]` in TOML.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BanList
+{
+    #[serde(default)]
+    pub banned: HashSet<String>,
+    #[serde(default)]
+    pub restricted: HashSet<String>,
+}
+
+impl BanList
+{
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<BanList, CardDatabaseError>
+    {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| CardDatabaseError::Io { path: path.to_path_buf(), error })?;
+        toml::from_str(&text)
+            .map_err(|e| CardDatabaseError::Parse { path: path.to_path_buf(), message: e.to_string() })
+    }
+}
+
+/// Format rules a deck has to satisfy.
+#[derive(Clone, Debug)]
+pub struct DeckValidator
+{
+    pub min_deck_size: usize,
+    pub max_deck_size: Option<usize>,
+    pub max_copies: Option<usize>,     // basic lands are exempt; 1 means singleton
+    pub max_sideboard: Option<usize>,
+    pub ban_list: BanList,
+}
+
+impl DeckValidator
+{
+    /// 60-card constructed: 4 copies, 15-card sideboard.
+    pub fn constructed() -> Self
+    {
+        DeckValidator
+        {
+            min_deck_size: 60,
+            max_deck_size: None,
+            max_copies: Some(4),
+            max_sideboard: Some(15),
+            ban_list: BanList::default(),
+        }
+    }
+
+    /// 100-card singleton without a sideboard.
+    pub fn singleton() -> Self
+    {
+        DeckValidator
+        {
+            min_deck_size: 100,
+            max_deck_size: Some(100),
+            max_copies: Some(1),
+            max_sideboard: Some(0),
+            ban_list: BanList::default(),
+        }
+    }
+
+    /// 40-card sealed or draft deck; the rest of the pool is the sideboard.
+    pub fn limited() -> Self
+    {
+        DeckValidator
+        {
+            min_deck_size: 40,
+            max_deck_size: None,
+            max_copies: None,
+            max_sideboard: None,
+            ban_list: BanList::default(),
+        }
+    }
+
+    /// Only a minimum size; used for the land-ratio experiments.
+    pub fn freeform(min_deck_size: usize) -> Self
+    {
+        DeckValidator
+        {
+            min_deck_size,
+            max_deck_size: None,
+            max_copies: None,
+            max_sideboard: None,
+            ban_list: BanList::default(),
+        }
+    }
+
+    pub fn with_ban_list(mut self, ban_list: BanList) -> Self
+    {
+        self.ban_list = ban_list;
+        self
+    }
+
+    pub fn validate(&self, deck: &Deck) -> Vec<DeckViolation>
+    {
+        let mut violations = Vec::new();
+
+        let count = deck.cards.len();
+        if count < self.min_deck_size
+        {
+            violations.push(DeckViolation::TooFewCards { count, minimum: self.min_deck_size });
+        }
+        if let Some(maximum) = self.max_deck_size
+            && count > maximum
+        {
+            violations.push(DeckViolation::TooManyCards { count, maximum });
+        }
+        if let Some(maximum) = self.max_sideboard
+            && deck.sideboard.len() > maximum
+        {
+            violations.push(DeckViolation::SideboardTooLarge { count: deck.sideboard.len(), maximum });
+        }
+
+        // Copy limits count the main deck and sideboard together
+        let mut copies: Vec<(&str, usize)> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for card in deck.cards.iter().chain(deck.sideboard.iter())
+        {
+            match index.get(card.name.as_str())
+            {
+                Some(&i) => copies[i].1 += 1,
+                None =>
+                {
+                    index.insert(card.name.as_str(), copies.len());
+                    copies.push((card.name.as_str(), 1));
+                }
+            }
+        }
+
+        for (name, count) in copies
+        {
+            if self.ban_list.banned.contains(name)
+            {
+                violations.push(DeckViolation::Banned { card: name.to_string() });
+            }
+            else if self.ban_list.restricted.contains(name) && count > 1
+            {
+                violations.push(DeckViolation::Restricted { card: name.to_string(), count });
+            }
+            else if let Some(limit) = self.max_copies
+                && count > limit
+                && !is_basic_land(name)
+            {
+                violations.push(DeckViolation::TooManyCopies { card: name.to_string(), count, limit });
+            }
+        }
+
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::card::{forest, grizzly_bears};
+
+    #[test]
+    fn example_deck_breaks_copy_limit_but_not_basics()
+    {
+        let deck = Deck::example();
+        let violations = DeckValidator::constructed().validate(&deck);
+        assert_eq!(violations, vec![DeckViolation::TooManyCopies { card: String::from("Grizzly Bears"), count: 31, limit: 4 }]);
+        assert!(DeckValidator::freeform(60).validate(&deck).is_empty());
+    }
+
+    #[test]
+    fn ban_list_and_sideboard_size()
+    {
+        let mut ban_list = BanList::default();
+        ban_list.banned.insert(String::from("Grizzly Bears"));
+        let validator = DeckValidator::constructed().with_ban_list(ban_list);
+
+        let mut deck = Deck { cards: (0..60).map(|_| forest()).collect(), sideboard: Vec::new() };
+        deck.sideboard = (0..16).map(|_| forest()).collect();
+        deck.sideboard.push(grizzly_bears());
+
+        let violations = validator.validate(&deck);
+        assert!(violations.contains(&DeckViolation::SideboardTooLarge { count: 17, maximum: 15 }));
+        assert!(violations.contains(&DeckViolation::Banned { card: String::from("Grizzly Bears") }));
+    }
+}
@@ -0,0 +1,46 @@
+use std::any::Any;
+use serde::{Deserialize, Serialize};
+use engine::{grizzly_bears, register_fragment, Card, CardFragmentKind, Fragment};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PoisonFragment {
+    counters: u8,
+}
+
+impl Fragment for PoisonFragment {
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+    fn box_clone(&self) -> Box<dyn Fragment> { Box::new(self.clone()) }
+}
+
+#[derive(Clone)]
+struct UnregisteredFragment;
+
+impl Fragment for UnregisteredFragment {
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+    fn box_clone(&self) -> Box<dyn Fragment> { Box::new(self.clone()) }
+}
+
+#[test]
+fn registered_fragment_roundtrips() {
+    register_fragment::<PoisonFragment>("Poison").unwrap();
+    assert!(register_fragment::<PoisonFragment>("Tappable").is_err());
+
+    let mut card = grizzly_bears();
+    card.fragments.insert(CardFragmentKind::Custom("Poison"), Box::new(PoisonFragment { counters: 3 }));
+
+    let json = serde_json::to_string(&card).expect("serialize card");
+    let card2: Card = serde_json::from_str(&json).expect("deserialize card");
+
+    let poison = card2.fragments.get(&CardFragmentKind::Custom("Poison")).unwrap();
+    assert_eq!(poison.as_any().downcast_ref::<PoisonFragment>().unwrap().counters, 3);
+    assert!(card2.fragments.contains_key(&CardFragmentKind::Tappable));
+}
+
+#[test]
+fn unregistered_fragment_fails_to_serialize() {
+    let mut card = grizzly_bears();
+    card.fragments.insert(CardFragmentKind::Custom("Mystery"), Box::new(UnregisteredFragment));
+    assert!(serde_json::to_string(&card).is_err());
+}
@@ -0,0 +1,92 @@
+use engine::{forest, grizzly_bears, Action, Deck, Decision, GameState, GameStep, RandomController, ScriptedController, Zone};
+
+fn deck(cards: Vec<engine::Card>) -> Deck {
+    Deck { cards, sideboard: Vec::new() }
+}
+
+fn forests(n: usize) -> Vec<engine::Card> {
+    (0..n).map(|_| forest()).collect()
+}
+
+/// Step until `turn` reaches `step` (or the game ends).
+fn play_until(game: &mut GameState, turn: u32, step: GameStep) {
+    while !(game.is_game_over() || game.turns == turn && game.step == step) {
+        game.step();
+    }
+}
+
+fn land(index: usize) -> Decision {
+    Decision::Act(Action::PlayLand { zone: Zone::Hand, index })
+}
+
+fn pass() -> Decision {
+    Decision::Act(Action::PassPriority)
+}
+
+#[test]
+fn scripted_bears_attack_on_turn_five() {
+    let mut cards = vec![forest(), grizzly_bears()];
+    cards.extend(forests(20));
+    let mut game = GameState::new_stacked(&[&deck(cards), &deck(forests(22))], 0);
+
+    game.set_controller(0, Box::new(ScriptedController::new([
+        land(0), pass(),                                                                    // turn 1
+        land(1),                                                                            // turn 3
+        Decision::Act(Action::Cast { zone: Zone::Hand, index: 0, payment: vec![0, 1] }),
+        pass(),
+        Decision::Act(Action::Attack(vec![])),                                              // still summoning sick
+        Decision::Act(Action::Attack(vec![2])),                                             // turn 5
+    ])));
+    game.set_controller(1, Box::new(ScriptedController::new([])));
+
+    play_until(&mut game, 4, GameStep::StartTurn);
+    let battlefield = &game.players[0].zones[&Zone::Battlefield];
+    assert_eq!(battlefield.len(), 3);
+    assert_eq!(battlefield[2].name, "Grizzly Bears");
+    assert_eq!(game.players[1].life, 20);
+
+    play_until(&mut game, 5, GameStep::EndTurn);
+    assert_eq!(game.players[1].life, 18);
+    assert_eq!(game.controllers[0].remaining(), 0);
+}
+
+#[test]
+#[should_panic(expected = "never used")]
+fn unused_script_fails_loudly() {
+    let mut game = GameState::new_stacked(&[&deck(forests(22)), &deck(forests(22))], 0);
+
+    // Written for a block, but nobody ever attacks player 1
+    let script = ScriptedController::new([Decision::Act(Action::Block(vec![(0, 0)]))]);
+    game.set_controller(1, Box::new(script.clone()));
+    play_until(&mut game, 3, GameStep::StartTurn);
+    assert_eq!(game.controllers[1].remaining(), 1);
+    script.assert_exhausted();
+}
+
+#[test]
+#[should_panic(expected = "is illegal")]
+fn scripted_illegal_action_panics() {
+    let mut cards = vec![grizzly_bears()];
+    cards.extend(forests(20));
+    let mut game = GameState::new_stacked(&[&deck(cards), &deck(forests(21))], 0);
+
+    // No lands to pay with on turn 1
+    game.set_controller(0, Box::new(ScriptedController::new([
+        Decision::Act(Action::Cast { zone: Zone::Hand, index: 0, payment: vec![] }),
+    ])));
+    play_until(&mut game, 2, GameStep::StartTurn);
+}
+
+#[test]
+fn random_controllers_finish_reproducibly() {
+    let run = |seed: u64| {
+        let mut game = GameState::new_with_decks(&[&Deck::example(), &Deck::example()], 0, seed);
+        game.set_controller(0, Box::new(RandomController::new(seed)));
+        game.set_controller(1, Box::new(RandomController::new(seed + 1)));
+        play_until(&mut game, u32::MAX, GameStep::GameOver);
+        (game.turns, game.winner(), game.players[0].life, game.players[1].life)
+    };
+
+    assert!(run(7).0 > 0);
+    assert_eq!(run(7), run(7));
+}
@@ -0,0 +1,42 @@
+use engine::{import_scryfall_bulk, CardDatabase, CardType, Rarity, SupportStatus};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/scryfall_sample.json");
+
+#[test]
+fn scryfall_fixture_maps_fields_and_support_status() {
+    let cards = import_scryfall_bulk(FIXTURE).expect("import fixture");
+
+    // Duplicate printings of Forest collapse into one entry
+    assert_eq!(cards.iter().filter(|c| c.definition.name == "Forest").count(), 1);
+
+    let status = |name: &str| cards.iter().find(|c| c.definition.name == name).unwrap().status.clone();
+    assert_eq!(status("Forest"), SupportStatus::Supported);
+    assert_eq!(status("Grizzly Bears"), SupportStatus::Supported);
+    // Vigilance is played; flying isn't yet, so the angel isn't a plain 4/4
+    assert_eq!(status("Steadfast Guard"), SupportStatus::Supported);
+    assert_eq!(status("Serra Angel"), SupportStatus::UnsupportedRulesText(vec![String::from("Flying")]));
+    assert!(matches!(status("Typhoid Rats"), SupportStatus::UnsupportedRulesText(lines) if lines[0].starts_with("Deathtouch")));
+    assert_eq!(status("Llanowar Elves"), SupportStatus::UnsupportedRulesText(vec![String::from("{T}: Add {G}.")]));
+    assert_eq!(status("Tarmogoyf"), SupportStatus::UnsupportedStats);
+    assert_eq!(status("Lightning Bolt"), SupportStatus::UnsupportedType(String::from("Instant")));
+    assert!(matches!(status("Delver of Secrets // Insectile Aberration"), SupportStatus::UnsupportedLayout(_)));
+
+    let angel = cards.iter().find(|c| c.definition.name == "Serra Angel").unwrap();
+    assert_eq!(angel.definition.keywords, vec![String::from("Flying"), String::from("Vigilance")]);
+    // Unplayable text stays out of the abilities the card would load with
+    let elves = cards.iter().find(|c| c.definition.name == "Llanowar Elves").unwrap();
+    assert!(elves.definition.abilities.is_empty());
+
+    let mut db = CardDatabase::new();
+    assert_eq!(db.import_scryfall(FIXTURE).unwrap(), 3);
+    assert!(db.create("Serra Angel").is_none());
+    assert!(db.create("Steadfast Guard").unwrap().has_keyword("vigilance"));
+
+    let bears = db.create("Grizzly Bears").unwrap();
+    assert!(bears.is_type(CardType::Creature));
+    assert_eq!(bears.cost, 2);
+    assert_eq!(engine::creature_stats(&bears).unwrap().power, 2);
+    assert_eq!(bears.rarity, Some(Rarity::Common));
+    assert!(bears.is_from_set("10e"));
+    assert_eq!(db.of_rarity(Rarity::Common).len(), 3);
+}
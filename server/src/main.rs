@@ -1,6 +1,6 @@
 use axum::{routing::{get, post}, Json, Router};
-use std::sync::{Arc, Mutex};
-use engine::{GameState, GameStep};
+use std::sync::{Arc, Mutex, RwLock};
+use engine::{Action, GameState, GameStep, CardDatabase, DeckFormat, DeckValidator, BanList, ArtManifest};
 use axum::extract::Extension;
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
@@ -83,6 +83,19 @@ fn kill_process_on_port(_port: u16) {
 async fn main()
 {
     let game = Arc::new(Mutex::new(GameState::new_default()));
+
+    // Card data and format rules for uploaded decks
+    let card_db = CardDatabase::load_dir(engine::card_data_dir_path()).unwrap_or_else(|e| {
+        eprintln!("Failed to load card data: {}", e);
+        CardDatabase::new()
+    });
+    let ban_list = BanList::load(engine::ban_list_path()).unwrap_or_else(|e| {
+        eprintln!("Failed to load ban list: {}", e);
+        BanList::default()
+    });
+    let validator = DeckValidator::constructed().with_ban_list(ban_list);
+    let art = Arc::new(RwLock::new(ArtManifest::scan(engine::card_images_dir_path())));
+
     let shutdown_flag = Arc::new(AtomicBool::new(false));
 
     // API routes
@@ -96,7 +109,12 @@ async fn main()
         .route("/restart", post(post_restart))
         .route("/declare-attackers", post(post_declare_attackers))
         .route("/declare-blockers", post(post_declare_blockers))
+        .route("/legal-actions/:player", get(get_legal_actions))
+        .route("/action", post(post_action))
+        .route("/upload-deck", post(post_upload_deck))
         .route("/music-list", get(get_music_list))
+        .route("/card-art", get(get_card_art))
+        .route("/card-art/:name", get(get_card_art_for))
         .route("/shutdown", post({
             let flag = shutdown_flag.clone();
             move || {
@@ -107,7 +125,10 @@ async fn main()
                 }
             }
         }))
-        .layer(Extension(game.clone()));
+        .layer(Extension(game.clone()))
+        .layer(Extension(Arc::new(card_db)))
+        .layer(Extension(Arc::new(validator)))
+        .layer(Extension(art));
 
     // Static routes for the web/ directory (simple handlers)
     let app = Router::new()
@@ -193,33 +214,97 @@ pub struct DeclareBlockersRequest {
 async fn post_declare_attackers(
     Extension(game): Extension<Arc<Mutex<GameState>>>,
     Json(payload): Json<DeclareAttackersRequest>,
-) -> Json<GameState> {
+) -> axum::response::Response {
     let mut g = game.lock().unwrap();
-    g.attacking_creatures = payload.attacking_indices;
-    
-    // Tap all attacking creatures
-    let attacking_to_tap = g.attacking_creatures.clone();
-    if let Some(battlefield) = g.zones_mut().get_mut(&engine::Zone::Battlefield) {
-        for idx in attacking_to_tap {
-            if idx < battlefield.len() {
-                engine::tappable::set_tapped(&mut battlefield[idx], true);
-            }
-        }
-    }
-    
-    g.step = GameStep::DeclareBlockers;
-    Json(g.clone())
+    let player = g.current_player_index;
+    apply_and_respond(&mut g, player, Action::Attack(payload.attacking_indices))
 }
 
 async fn post_declare_blockers(
     Extension(game): Extension<Arc<Mutex<GameState>>>,
     Json(payload): Json<DeclareBlockersRequest>,
-) -> Json<GameState> {
+) -> axum::response::Response {
     let mut g = game.lock().unwrap();
-    g.blocking_map = payload.blocking_map;
-    g.step = GameStep::AssignDamage;
-    Json(g.clone())
+    let player = g.defending_player();
+    let mut blocks: Vec<(usize, usize)> = payload.blocking_map.into_iter().collect();
+    blocks.sort_unstable();
+    apply_and_respond(&mut g, player, Action::Block(blocks))
 }
+
+// The new state on success, 422 with the reason when the action is illegal.
+fn apply_and_respond(g: &mut GameState, player: usize, action: Action) -> axum::response::Response {
+    match g.apply_action(player, action) {
+        Ok(()) => Json(g.clone()).into_response(),
+        Err(e) => (StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+async fn get_legal_actions(
+    Extension(game): Extension<Arc<Mutex<GameState>>>,
+    Path(player): Path<usize>,
+) -> Json<Vec<Action>> {
+    let g = game.lock().unwrap();
+    Json(g.legal_actions(player))
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ActionRequest {
+    pub player: usize,
+    pub action: Action,
+}
+
+async fn post_action(
+    Extension(game): Extension<Arc<Mutex<GameState>>>,
+    Json(payload): Json<ActionRequest>,
+) -> axum::response::Response {
+    let mut g = game.lock().unwrap();
+    apply_and_respond(&mut g, payload.player, payload.action)
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct UploadDeckRequest {
+    pub deck_list: String,
+    #[serde(default)]
+    pub format: Option<String>, // "text", "arena" or "mtgo"; detected when missing
+}
+
+async fn post_upload_deck(
+    Extension(game): Extension<Arc<Mutex<GameState>>>,
+    Extension(card_db): Extension<Arc<CardDatabase>>,
+    Extension(validator): Extension<Arc<DeckValidator>>,
+    Json(payload): Json<UploadDeckRequest>,
+) -> axum::response::Response {
+    let format = match payload.format.as_deref() {
+        Some("text") => DeckFormat::Text,
+        Some("arena") => DeckFormat::Arena,
+        Some("mtgo") => DeckFormat::Mtgo,
+        _ => DeckFormat::detect(&payload.deck_list),
+    };
+
+    let deck = match engine::parse_deck(&payload.deck_list, format, &card_db) {
+        Ok(deck) => deck,
+        Err(errors) => {
+            let errors: Vec<_> = errors.iter()
+                .map(|e| serde_json::json!({ "line": e.line, "message": e.message }))
+                .collect();
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "errors": errors }))).into_response();
+        }
+    };
+
+    let violations = validator.validate(&deck);
+    if !violations.is_empty() {
+        let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({
+            "violations": violations,
+            "messages": messages
+        }))).into_response();
+    }
+
+    let mut g = game.lock().unwrap();
+    *g = GameState::new(2, &deck);
+    Json(g.clone()).into_response()
+}
+
 async fn get_state(Extension(game): Extension<Arc<Mutex<GameState>>>) -> Json<GameState> {
     Json(game.lock().unwrap().clone())
 }
@@ -326,6 +411,34 @@ async fn get_music_list() -> Json<serde_json::Value> {
     }))
 }
 
+/// Rebuild the art manifest if images were added or removed since the last scan
+fn current_art(art: &RwLock<ArtManifest>) -> ArtManifest {
+    if art.read().unwrap().is_stale() {
+        art.write().unwrap().refresh();
+    }
+    art.read().unwrap().clone()
+}
+
+async fn get_card_art(Extension(art): Extension<Arc<RwLock<ArtManifest>>>) -> Json<ArtManifest> {
+    Json(current_art(&art))
+}
+
+async fn get_card_art_for(
+    Extension(art): Extension<Arc<RwLock<ArtManifest>>>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let manifest = current_art(&art);
+    match manifest.lookup(&name) {
+        Some(image) => Json(serde_json::json!({
+            "name": name,
+            "url": image.url,
+            "mime_type": image.mime_type,
+            "fallback": !manifest.has_art(&name),
+        })).into_response(),
+        None => (StatusCode::NOT_FOUND, "No art for card").into_response(),
+    }
+}
+
 async fn index() -> impl IntoResponse {
     match tokio::fs::read_to_string(web_path("web/index.html")).await {
         Ok(s) => ([("content-type", "text/html; charset=utf-8")], s).into_response(),
@@ -354,8 +467,12 @@ async fn serve_card(Path(file): Path<String>) -> impl IntoResponse
         return (StatusCode::BAD_REQUEST, "Invalid path").into_response();
     }
 
+    let Some(content_type) = engine::image_mime_type(&file) else {
+        return (StatusCode::NOT_FOUND, "Not Found").into_response();
+    };
+
     match tokio::fs::read(web_path(&format!("web/cards/{}", file))).await {
-        Ok(bytes) => ([("content-type", "application/octet-stream")], bytes).into_response(),
+        Ok(bytes) => ([("content-type", content_type)], bytes).into_response(),
         Err(_) => (StatusCode::NOT_FOUND, "Not Found").into_response(),
     }
 }
@@ -385,4 +502,23 @@ async fn serve_music(Path(file): Path<String>) -> impl IntoResponse
         Ok(bytes) => ([("content-type", content_type)], bytes).into_response(),
         Err(_) => (StatusCode::NOT_FOUND, "Not Found").into_response(),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::Effect;
+
+    #[tokio::test]
+    async fn turn_response_includes_the_turn_queue() {
+        let mut g = GameState::new_default();
+        g.resolve_effects(0, &[Effect::ExtraTurn, Effect::ExtraTurn, Effect::Skip(GameStep::Draw)]);
+        let game = Arc::new(Mutex::new(g));
+
+        let Json(after) = post_turn(Extension(game)).await;
+        let body = serde_json::to_value(&after).unwrap();
+        assert_eq!(body["turn_queue"]["extra_turns"], serde_json::json!(after.turn_queue.extra_turns));
+        assert!(!after.turn_queue.extra_turns.is_empty());
+        assert_eq!(body["turn_queue"]["skips"].as_array().map(Vec::len), Some(after.turn_queue.skips.len()));
+    }
+}
@@ -6,6 +6,33 @@ let selectedAttackers = new Set();
 let selectedBlockers = new Map(); // blocker index -> attacker index
 let currentGameState = null;
 
+// Card name -> image, fetched from the server so we never guess file names
+let artManifest = { images: {}, fallback: null };
+
+async function loadArtManifest()
+{
+    try
+    {
+        const res = await fetch(`${API_PREFIX}/card-art`);
+        artManifest = await res.json();
+    }
+    catch (e)
+    {
+        console.error("Failed to load card art manifest:", e);
+    }
+}
+
+function cardBackUrl()
+{
+    return artManifest.fallback ? artManifest.fallback.url : '/cards/back.jpg';
+}
+
+function cardImageUrl(name)
+{
+    const image = artManifest.images[name];
+    return image ? image.url : cardBackUrl();
+}
+
 async function fetchState()
 {
     try 
@@ -145,6 +172,10 @@ async function declareAttackers() {
             body: JSON.stringify(payload)
         });
         const newState = await response.json();
+        if (!response.ok) {
+            console.error("Illegal attacker declaration:", newState.error);
+            return;
+        }
         selectedAttackers.clear();
         updateDisplay(newState);
     } catch (e) {
@@ -165,6 +196,10 @@ async function declareBlockers() {
             body: JSON.stringify(payload)
         });
         const newState = await response.json();
+        if (!response.ok) {
+            console.error("Illegal blocker declaration:", newState.error);
+            return;
+        }
         selectedBlockers.clear();
         updateDisplay(newState);
     } catch (e) {
@@ -321,7 +356,7 @@ function renderPlayerZones(player, playerIndex, isCurrentPlayer)
         const card = g.card;
         const creatureIndex = g.index;
         const img = document.createElement("img");
-        img.src = `/cards/${encodeURIComponent(card.name)}.jpg`;
+        img.src = cardImageUrl(card.name);
         img.className = "card";
         img.alt = card.name;
         img.dataset.creatureIndex = creatureIndex;
@@ -401,7 +436,7 @@ function renderPlayerZones(player, playerIndex, isCurrentPlayer)
         const startX = (containerW - totalWidth) / 2;
         untappedForests.forEach((card, i) => {
             const img = document.createElement("img");
-            img.src = `/cards/${encodeURIComponent(card.name)}.jpg`;
+            img.src = cardImageUrl(card.name);
             img.className = "card";
             img.alt = card.name;
 
@@ -434,7 +469,7 @@ function renderPlayerZones(player, playerIndex, isCurrentPlayer)
         const stackXBase = untappedForests.length > 0 ? ( (containerW + ((untappedForests.length - 1) * overlap + CARD_W)) / 2 + 8 ) : (containerW / 2 - CARD_W / 2);
         tappedForests.forEach((card, i) => {
             const img = document.createElement("img");
-            img.src = `/cards/${encodeURIComponent(card.name)}.jpg`;
+            img.src = cardImageUrl(card.name);
             img.className = "card";
             img.alt = card.name;
 
@@ -488,7 +523,7 @@ function renderPlayerZones(player, playerIndex, isCurrentPlayer)
     libraryCards.forEach((card, i) => 
     {
         const img = document.createElement("img");
-        img.src = `/cards/back.jpg`;
+        img.src = cardBackUrl();
         img.className = "card back";
         img.alt = "card back";
         img.style.width = `${LIB_CARD_W}px`;
@@ -535,7 +570,7 @@ function renderPlayerZones(player, playerIndex, isCurrentPlayer)
     graveyardCards.forEach((card, i) => 
     {
         const img = document.createElement("img");
-        img.src = `/cards/${encodeURIComponent(card.name)}.jpg`;
+        img.src = cardImageUrl(card.name);
         img.className = "card";
         img.alt = card.name;
         img.style.width = `${GY_CARD_W}px`;
@@ -594,7 +629,7 @@ function renderPlayerZones(player, playerIndex, isCurrentPlayer)
     {
         const img = document.createElement("img");
             // Use back of card for inactive player's hand
-            img.src = isCurrentPlayer ? `/cards/${encodeURIComponent(card.name)}.jpg` : `/cards/back.jpg`;
+            img.src = isCurrentPlayer ? cardImageUrl(card.name) : cardBackUrl();
         img.className = "card";
         img.alt = card.name;
         img.style.width = `${CARD_W}px`;
@@ -692,6 +727,9 @@ function updateDisplay(state)
     phaseElement.textContent = formatPhase(state.step);
     currentPlayerElement.textContent = state.current_player_index;
     turnsElement.textContent = state.turns;
+    if (state.turn_queue && state.turn_queue.extra_turns.length > 0) {
+        turnsElement.textContent += ` (extra turns queued: ${state.turn_queue.extra_turns.map(p => `Player ${p}`).join(', ')})`;
+    }
     
     // Display all players' health
     if (state.players && state.players.length > 0) {
@@ -910,7 +948,7 @@ function toggleMusic()
 }
 
 // Initial render and setup
-render();
+loadArtManifest().then(render);
 updateDeckInfo();
 
 // Load and play music